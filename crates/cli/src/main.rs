@@ -0,0 +1,465 @@
+//! `bestsign`, the command line tool for provenance logs.
+//!
+//! Plogs are kept in files in the envelope format, and the secret keys of every plog
+//! in one encrypted keystore, under a label for the plog. Everything works offline.
+
+use bestsign_core::envelope::{decode_log, encode_log};
+use bestsign_core::json::{log_from_json_str, log_to_json_string};
+use bestsign_core::ops::config::defaults::DEFAULT_PUBKEY;
+use bestsign_core::ops::config::{LockScript, UnlockScript};
+use bestsign_core::ops::open::config::{Config, NewLogBuilder};
+use bestsign_core::ops::update::{OpParams, UpdateConfig};
+use bestsign_core::ops::{create, update_plog};
+use bestsign_core::provenance_log::{Key, Log, Script};
+use bestsign_core::utils::{decode_vlad, get_display_data};
+use bestsign_core::{multicid::EncodedCid, Base, Cid, Codec, Keystore, VerificationReport};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tracing_subscriber::EnvFilter;
+
+/// The entry lock script used when none is given
+const DEFAULT_LOCK: &str = r#"
+    check_signature("/recoverykey", "/entry/") ||
+    check_signature("/pubkey", "/entry/") ||
+    check_preimage("/hash")
+"#;
+
+/// The entry unlock script used when none is given
+const DEFAULT_UNLOCK: &str = r#"
+    push("/entry/");
+    push("/entry/proof");
+"#;
+
+#[derive(Parser)]
+#[command(
+    name = "bestsign",
+    version,
+    about = "Create, update and verify provenance logs"
+)]
+struct Cli {
+    /// The keystore file, created by the first `create` if missing
+    #[arg(
+        long,
+        global = true,
+        env = "BESTSIGN_KEYSTORE",
+        default_value = "bestsign.keystore"
+    )]
+    keystore: PathBuf,
+    /// A file holding the keystore password, otherwise it is read from `BESTSIGN_PASSWORD`
+    #[arg(long, global = true)]
+    password_file: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a new plog
+    Create {
+        /// The plog file to write
+        plog: PathBuf,
+        /// A JSON open config, all other create options are ignored when given
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// The entry lock script file
+        #[arg(long)]
+        lock: Option<PathBuf>,
+        /// The entry unlock script file
+        #[arg(long)]
+        unlock: Option<PathBuf>,
+        /// The codec of the Vlad key, the entry key and the pubkey, such as `secp256k1-priv`
+        #[arg(long, default_value = "ed25519-priv")]
+        codec: String,
+        /// The keystore label of the plog, defaults to the plog file name
+        #[arg(long)]
+        label: Option<String>,
+        /// Overwrite the plog file and replace the keys stored under the label
+        #[arg(long)]
+        force: bool,
+    },
+    /// Append an entry to a plog
+    Update {
+        /// The plog file to update
+        plog: PathBuf,
+        /// Set a string value, as `/key/path=value`
+        #[arg(long = "str", value_name = "KEY=VALUE")]
+        strs: Vec<String>,
+        /// Delete a key-path
+        #[arg(long, value_name = "KEY")]
+        delete: Vec<String>,
+        /// Generate a new key, as `/key/path` or `/key/path=codec`
+        #[arg(long, value_name = "KEY[=CODEC]")]
+        keygen: Vec<String>,
+        /// A JSON file with a list of ops, applied after the flag ops
+        #[arg(long)]
+        ops: Option<PathBuf>,
+        /// The entry unlock script file
+        #[arg(long)]
+        unlock: Option<PathBuf>,
+        /// The key-path of the key signing the entry
+        #[arg(long, default_value = DEFAULT_PUBKEY)]
+        signing_key: String,
+        /// The keystore label of the plog, defaults to the plog file name
+        #[arg(long)]
+        label: Option<String>,
+    },
+    /// Print the verified state of a plog
+    Show {
+        /// The plog file
+        plog: PathBuf,
+    },
+    /// Verify a plog, reporting on every entry
+    Verify {
+        /// The plog file
+        plog: PathBuf,
+    },
+    /// Export a plog
+    Export {
+        /// The plog file
+        plog: PathBuf,
+        /// The export format
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// The file to write, defaults to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import a plog from JSON or any binary encoding, verifying it
+    Import {
+        /// The file to import
+        input: PathBuf,
+        /// The plog file to write
+        plog: PathBuf,
+    },
+    /// Keystore management
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+    /// Vlad utilities
+    Vlad {
+        #[command(subcommand)]
+        command: VladCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// List the stored keys by label and key-path, no password needed
+    List,
+    /// Print a secret key, multibase encoded, for backup
+    Export {
+        /// The plog label
+        label: String,
+        /// The key-path, such as `/pubkey`
+        key_path: String,
+    },
+    /// Delete a secret key
+    Delete {
+        /// The plog label
+        label: String,
+        /// The key-path, such as `/pubkey`
+        key_path: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum VladCommand {
+    /// Decode a multibase Vlad and print its bytes as hex
+    Decode {
+        /// The multibase encoded Vlad
+        vlad: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Lossless DAG-JSON
+    Json,
+    /// The binary envelope
+    Envelope,
+}
+
+fn main() -> ExitCode {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .try_init();
+
+    match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let keystore = KeystoreArgs {
+        path: cli.keystore,
+        password_file: cli.password_file,
+    };
+    match cli.command {
+        Command::Create {
+            plog,
+            config,
+            lock,
+            unlock,
+            codec,
+            label,
+            force,
+        } => {
+            if plog.exists() && !force {
+                return Err(format!(
+                    "{} already exists, pass --force to overwrite it",
+                    plog.display()
+                )
+                .into());
+            }
+            let config: Config = match config {
+                Some(config) => serde_json::from_str(&std::fs::read_to_string(config)?)?,
+                None => {
+                    let mut builder = NewLogBuilder::new(
+                        LockScript(read_script(lock.as_deref(), DEFAULT_LOCK)?),
+                        UnlockScript(read_script(unlock.as_deref(), DEFAULT_UNLOCK)?),
+                    );
+                    builder.with_key_codec(Codec::try_from(codec.as_str())?);
+                    builder.build()
+                }
+            };
+            let mut keystore = keystore.open(true)?;
+            let label = plog_label(&plog, label)?;
+            if keystore.list().iter().any(|(stored, _)| *stored == label) && !force {
+                return Err(format!(
+                    "The keystore already has keys for {label}, pass --force to replace them"
+                )
+                .into());
+            }
+            // the old keys stay in the file until the new plog is written
+            keystore.remove_label(&label)?;
+            keystore.select(label);
+            let log = create(&config, &mut keystore)?;
+            write_plog(&plog, &log)?;
+            keystore.save()?;
+            println!("{}", encoded_cid(&log.head));
+        }
+        Command::Update {
+            plog,
+            strs,
+            delete,
+            keygen,
+            ops,
+            unlock,
+            signing_key,
+            label,
+        } => {
+            let mut log = read_plog(&plog)?;
+            let mut keystore = keystore.open(false)?;
+            let label = plog_label(&plog, label)?;
+            let signing_key = keystore.get(&label, &signing_key)?;
+            keystore.select(label);
+
+            let mut config =
+                UpdateConfig::new(read_script(unlock.as_deref(), DEFAULT_UNLOCK)?, signing_key);
+            for op in parse_ops(&strs, &delete, &keygen)? {
+                config.add_op(op);
+            }
+            if let Some(ops) = ops {
+                let ops: Vec<OpParams> = serde_json::from_str(&std::fs::read_to_string(ops)?)?;
+                for op in ops {
+                    config.add_op(op);
+                }
+            }
+
+            update_plog(&mut log, &config.build(), &mut keystore)?;
+            write_plog(&plog, &log)?;
+            keystore.save()?;
+            println!("{}", encoded_cid(&log.head));
+        }
+        Command::Show { plog } => {
+            let display = get_display_data(&read_plog(&plog)?)?;
+            println!("{}", serde_json::to_string_pretty(&display)?);
+        }
+        Command::Verify { plog } => {
+            let report = VerificationReport::new(&read_plog(&plog)?);
+            for entry in &report.entries {
+                println!(
+                    "{:>6}  {:<10}  {}  candidate-lock={}{}",
+                    entry.seqno,
+                    format!("{:?}", entry.status),
+                    encoded_cid(&entry.cid),
+                    entry.candidate_lock.as_deref().unwrap_or("-"),
+                    entry
+                        .error
+                        .as_ref()
+                        .map(|e| format!("  error={e}"))
+                        .unwrap_or_default(),
+                );
+            }
+            if !report.is_valid() {
+                println!("invalid");
+                return Ok(ExitCode::FAILURE);
+            }
+            println!("valid");
+        }
+        Command::Export {
+            plog,
+            format,
+            output,
+        } => {
+            let log = read_plog(&plog)?;
+            let bytes = match format {
+                Format::Json => log_to_json_string(&log)?.into_bytes(),
+                Format::Envelope => encode_log(&log),
+            };
+            match output {
+                Some(output) => std::fs::write(output, bytes)?,
+                None => {
+                    use std::io::Write;
+                    std::io::stdout().write_all(&bytes)?;
+                }
+            }
+        }
+        Command::Import { input, plog } => {
+            let bytes = std::fs::read(input)?;
+            let log = match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+                Some(b'{') => log_from_json_str(std::str::from_utf8(&bytes)?)?,
+                _ => decode_log(&bytes)?,
+            };
+            let report = VerificationReport::new(&log);
+            if let Some(failure) = report.first_failure() {
+                return Err(format!(
+                    "The plog fails to verify at seqno {}: {}",
+                    failure.seqno,
+                    failure.error.as_deref().unwrap_or_default()
+                )
+                .into());
+            }
+            write_plog(&plog, &log)?;
+            println!("{}", encoded_cid(&log.head));
+        }
+        Command::Keys {
+            command: KeysCommand::List,
+        } => {
+            for (label, key_path) in Keystore::open(&keystore.path)?.list() {
+                println!("{label}\t{key_path}");
+            }
+        }
+        Command::Keys {
+            command: KeysCommand::Export { label, key_path },
+        } => {
+            let exported = keystore.open(false)?.export(&label, &key_path)?;
+            println!("{}", exported.as_str());
+        }
+        Command::Keys {
+            command: KeysCommand::Delete { label, key_path },
+        } => {
+            if !keystore.open(false)?.delete(&label, &key_path)? {
+                return Err(format!("No key stored for {label}{key_path}").into());
+            }
+        }
+        Command::Vlad {
+            command: VladCommand::Decode { vlad },
+        } => {
+            let bytes = decode_vlad(&vlad)?;
+            let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            println!("{hex}");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Where the keystore is and how to unlock it
+struct KeystoreArgs {
+    path: PathBuf,
+    password_file: Option<PathBuf>,
+}
+
+impl KeystoreArgs {
+    /// Opens and unlocks the keystore, creating it first if allowed
+    fn open(&self, create_missing: bool) -> Result<Keystore, Box<dyn std::error::Error>> {
+        let password = match &self.password_file {
+            Some(path) => std::fs::read_to_string(path)?.trim_end().to_string(),
+            None => std::env::var("BESTSIGN_PASSWORD")
+                .map_err(|_| "Set BESTSIGN_PASSWORD or pass --password-file")?,
+        };
+        if create_missing && !self.path.exists() {
+            return Ok(Keystore::create(&self.path, password.as_bytes())?);
+        }
+        let mut keystore = Keystore::open(&self.path)?;
+        keystore.unlock(password.as_bytes())?;
+        Ok(keystore)
+    }
+}
+
+/// The keystore label of the plog
+fn plog_label(plog: &Path, label: Option<String>) -> Result<String, Box<dyn std::error::Error>> {
+    match label {
+        Some(label) => Ok(label),
+        None => Ok(plog
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or("The plog file has no name to use as label, pass --label")?
+            .to_string()),
+    }
+}
+
+fn read_plog(path: &Path) -> Result<Log, Box<dyn std::error::Error>> {
+    Ok(decode_log(&std::fs::read(path)?)?)
+}
+
+fn write_plog(path: &Path, log: &Log) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(std::fs::write(path, encode_log(log))?)
+}
+
+/// Reads a script file, or uses the default script
+fn read_script(path: Option<&Path>, default: &str) -> Result<Script, Box<dyn std::error::Error>> {
+    let code = match path {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => default.to_string(),
+    };
+    Ok(Script::Code(Key::default(), code))
+}
+
+/// Turns the update flags into ops
+fn parse_ops(
+    strs: &[String],
+    delete: &[String],
+    keygen: &[String],
+) -> Result<Vec<OpParams>, Box<dyn std::error::Error>> {
+    let mut ops = Vec::new();
+    for s in strs {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected KEY=VALUE, got {s}"))?;
+        ops.push(OpParams::UseStr {
+            key: Key::try_from(key)?,
+            s: value.to_string(),
+        });
+    }
+    for key in delete {
+        ops.push(OpParams::Delete {
+            key: Key::try_from(key.as_str())?,
+        });
+    }
+    for k in keygen {
+        let (key, codec) = match k.split_once('=') {
+            Some((key, codec)) => (key, Codec::try_from(codec)?),
+            None => (k.as_str(), Codec::Ed25519Priv),
+        };
+        ops.push(OpParams::KeyGen {
+            key: Key::try_from(key)?,
+            codec,
+            threshold: 1,
+            limit: 1,
+            revoke: false,
+        });
+    }
+    Ok(ops)
+}
+
+fn encoded_cid(cid: &Cid) -> String {
+    EncodedCid::new(Base::Base32Lower, cid.clone()).to_string()
+}
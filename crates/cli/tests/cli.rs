@@ -0,0 +1,299 @@
+//! Drives the `bestsign` binary end to end.
+use bestsign_core::ops::config::{LockScript, UnlockScript};
+use bestsign_core::ops::open::config::NewLogBuilder;
+use bestsign_core::ops::update::OpParams;
+use bestsign_core::provenance_log::{Key, Script};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const LOCK: &str = r#"check_signature("/pubkey", "/entry/")"#;
+
+const UNLOCK: &str = r#"
+    push("/entry/");
+    push("/entry/proof");
+"#;
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bestsign-cli-{}", rand::random::<u64>()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const PASSWORD: &str = "test password";
+
+/// Runs the binary with the keystore in the dir
+fn bestsign(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bestsign"))
+        .args(args)
+        .env("BESTSIGN_KEYSTORE", dir.join("test.keystore"))
+        .env("BESTSIGN_PASSWORD", PASSWORD)
+        .output()
+        .expect("failed to run bestsign")
+}
+
+/// Runs the binary and returns its stdout, failing the test if the command fails
+fn ok(dir: &Path, args: &[&str]) -> String {
+    let output = bestsign(dir, args);
+    assert!(
+        output.status.success(),
+        "bestsign {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn path(p: &Path) -> &str {
+    p.to_str().unwrap()
+}
+
+#[test]
+fn test_create_update_verify() {
+    let dir = temp_dir();
+    let plog = dir.join("test.plog");
+    let lock = dir.join("lock.rhai");
+    let unlock = dir.join("unlock.rhai");
+    std::fs::write(&lock, LOCK).unwrap();
+    std::fs::write(&unlock, UNLOCK).unwrap();
+
+    let head = ok(
+        &dir,
+        &[
+            "create",
+            path(&plog),
+            "--lock",
+            path(&lock),
+            "--unlock",
+            path(&unlock),
+        ],
+    );
+    assert!(plog.exists());
+    assert!(dir.join("test.keystore").exists());
+
+    let new_head = ok(
+        &dir,
+        &[
+            "update",
+            path(&plog),
+            "--str",
+            "/hello/=World!",
+            "--unlock",
+            path(&unlock),
+        ],
+    );
+    assert_ne!(head, new_head);
+
+    // a second update with an ops file
+    let ops = dir.join("ops.json");
+    let delete = vec![OpParams::Delete {
+        key: Key::try_from("/hello/").unwrap(),
+    }];
+    std::fs::write(&ops, serde_json::to_string(&delete).unwrap()).unwrap();
+    ok(&dir, &["update", path(&plog), "--ops", path(&ops)]);
+
+    let report = ok(&dir, &["verify", path(&plog)]);
+    assert_eq!(report.lines().count(), 4);
+    assert_eq!(report.lines().last(), Some("valid"));
+    assert!(!report.contains("Failed"));
+
+    let show: serde_json::Value = serde_json::from_str(&ok(&dir, &["show", path(&plog)])).unwrap();
+    assert!(show.is_object());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_create_refuses_to_overwrite() {
+    let dir = temp_dir();
+    let plog = dir.join("once.plog");
+    let head = ok(&dir, &["create", path(&plog)]);
+
+    // the plog file exists
+    assert!(!bestsign(&dir, &["create", path(&plog)]).status.success());
+    assert_eq!(
+        ok(&dir, &["verify", path(&plog)]).lines().last(),
+        Some("valid")
+    );
+
+    // the keystore has keys for the label, even without the plog file
+    std::fs::remove_file(&plog).unwrap();
+    let output = bestsign(&dir, &["create", path(&plog)]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+
+    // a forced create that cannot write the plog keeps the old keys
+    let unwritable = dir.join("missing").join("once.plog");
+    assert!(!bestsign(&dir, &["create", path(&unwritable), "--force"])
+        .status
+        .success());
+    assert!(!bestsign(&dir, &["create", path(&plog)]).status.success());
+
+    // forced, the keys are replaced
+    let new_head = ok(&dir, &["create", path(&plog), "--force"]);
+    assert_ne!(new_head, head);
+    ok(&dir, &["update", path(&plog), "--str", "/hello/=World!"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_create_from_config() {
+    let dir = temp_dir();
+    let plog = dir.join("config.plog");
+    let config_path = dir.join("config.json");
+
+    let config = NewLogBuilder::new(
+        LockScript(Script::Code(Key::default(), LOCK.to_string())),
+        UnlockScript(Script::Code(Key::default(), UNLOCK.to_string())),
+    )
+    .build();
+    std::fs::write(&config_path, serde_json::to_string(&config).unwrap()).unwrap();
+
+    ok(
+        &dir,
+        &["create", path(&plog), "--config", path(&config_path)],
+    );
+    ok(&dir, &["update", path(&plog), "--str", "/name/=config"]);
+    assert_eq!(
+        ok(&dir, &["verify", path(&plog)]).lines().last(),
+        Some("valid")
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_create_with_codec() {
+    let dir = temp_dir();
+    let plog = dir.join("codec.plog");
+    ok(&dir, &["create", path(&plog), "--codec", "secp256k1-priv"]);
+    ok(&dir, &["update", path(&plog), "--str", "/hello/=World!"]);
+    assert_eq!(
+        ok(&dir, &["verify", path(&plog)]).lines().last(),
+        Some("valid")
+    );
+
+    assert!(!bestsign(
+        &dir,
+        &["create", path(&dir.join("bad.plog")), "--codec", "nope"]
+    )
+    .status
+    .success());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_export_import() {
+    let dir = temp_dir();
+    let plog = dir.join("export.plog");
+    ok(&dir, &["create", path(&plog)]);
+    ok(&dir, &["update", path(&plog), "--str", "/hello/=World!"]);
+
+    // through JSON
+    let json = dir.join("export.json");
+    ok(&dir, &["export", path(&plog), "-o", path(&json)]);
+    let imported = dir.join("imported.plog");
+    ok(&dir, &["import", path(&json), path(&imported)]);
+    assert_eq!(
+        std::fs::read(&plog).unwrap(),
+        std::fs::read(&imported).unwrap()
+    );
+
+    // through the envelope
+    let envelope = dir.join("export.bin");
+    ok(
+        &dir,
+        &[
+            "export",
+            path(&plog),
+            "--format",
+            "envelope",
+            "-o",
+            path(&envelope),
+        ],
+    );
+    ok(&dir, &["import", path(&envelope), path(&imported)]);
+    assert_eq!(
+        ok(&dir, &["verify", path(&imported)]).lines().last(),
+        Some("valid")
+    );
+
+    // garbage does not import
+    let garbage = dir.join("garbage");
+    std::fs::write(&garbage, "not a plog").unwrap();
+    assert!(
+        !bestsign(&dir, &["import", path(&garbage), path(&imported)])
+            .status
+            .success()
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_keys() {
+    let dir = temp_dir();
+    let plog = dir.join("keys.plog");
+    ok(&dir, &["create", path(&plog)]);
+
+    // the keys are listed by label without a password
+    let list = Command::new(env!("CARGO_BIN_EXE_bestsign"))
+        .args(["keys", "list"])
+        .env("BESTSIGN_KEYSTORE", dir.join("test.keystore"))
+        .env_remove("BESTSIGN_PASSWORD")
+        .output()
+        .unwrap();
+    assert!(list.status.success());
+    let list = String::from_utf8(list.stdout).unwrap();
+    assert!(list.lines().any(|line| line == "keys\t/pubkey"));
+
+    let exported = ok(&dir, &["keys", "export", "keys", "/pubkey"]);
+    assert!(exported.trim().starts_with('z'));
+
+    // a wrong password unlocks nothing
+    let password = dir.join("password");
+    std::fs::write(&password, "wrong").unwrap();
+    let output = bestsign(
+        &dir,
+        &[
+            "--password-file",
+            path(&password),
+            "keys",
+            "export",
+            "keys",
+            "/pubkey",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("password"));
+
+    // another label has no keys
+    let output = bestsign(
+        &dir,
+        &["update", path(&plog), "--label", "other", "--str", "/a/=b"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("/pubkey"));
+
+    // without the signing key there are no more updates
+    ok(&dir, &["keys", "delete", "keys", "/pubkey"]);
+    assert!(!bestsign(&dir, &["update", path(&plog), "--str", "/a/=b"])
+        .status
+        .success());
+    assert!(!bestsign(&dir, &["keys", "delete", "keys", "/pubkey"])
+        .status
+        .success());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_vlad_decode() {
+    let dir = std::env::temp_dir();
+    let vlad = "kg0nc6q8quc8yyiv9a6u4py2gcmxk8kfcomc3zxy2entacok7w9p6uh8yuq9ogclp9lc2idi8xfdggj4nr1d0u0clrtsj5p6y8tsbvslxzcppoofbg098wek6yrwrjp1bx4nhz5wpsbxkqb0qyclyog8jgbcz3t5v0uju8tmpt3na0c56oz";
+    let hex = ok(&dir, &["vlad", "decode", vlad]);
+    assert_eq!(hex.trim().len(), 115 * 2);
+
+    assert!(!bestsign(&dir, &["vlad", "decode", "nope"]).status.success());
+}
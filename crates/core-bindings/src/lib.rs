@@ -1,6 +1,11 @@
+use bestsign_core::diff::{diff_logs_with, diff_seqnos_with};
+use bestsign_core::envelope::{decode_log, encode_log};
+use bestsign_core::history::{key_history, state_at_seqno};
+use bestsign_core::json::{log_from_json_str, log_to_json_string};
 use bestsign_core::ops::config::defaults::DEFAULT_PUBKEY;
-use bestsign_core::utils::get_display_data;
+use bestsign_core::utils::{get_display_data_with, get_timeline_with, key_codec, DisplayOptions};
 use bestsign_core::{
+    error::CryptoError,
     ops::{
         config::{
             CidGen, KeyParams, LockScript, UnlockScript, UseStr, VladCid, VladConfig, VladKey,
@@ -8,10 +13,10 @@ use bestsign_core::{
         create,
         open::config::NewLogBuilder,
         update::UpdateConfig,
-        update_plog, CryptoManager,
+        update_plog, CryptoManager, SigningContext,
     },
     provenance_log::{Key, Log, Script},
-    Codec, Multikey, Multisig,
+    Base, Codec, Multikey, Multisig, VerificationReport,
 };
 use js_sys::Function;
 use serde::{Deserialize, Serialize};
@@ -38,6 +43,115 @@ pub struct KeyArgs {
 pub struct SignArgs {
     mk: Multikey,
     data: Vec<u8>,
+    /// What the data is, for the wallet to confirm with the user
+    context: Option<SigningContext>,
+}
+
+/// The display options, every field is optional and falls back to the default
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DisplayOptionsArgs {
+    /// The multibase code character, such as "z" for base58btc or "b" for base32
+    base: Option<String>,
+    /// The fingerprint hash codec name, such as "sha2-256"
+    fingerprint_codec: Option<String>,
+    include_bytes: Option<bool>,
+    include_key_material: Option<bool>,
+}
+
+impl TryFrom<DisplayOptionsArgs> for DisplayOptions {
+    type Error = JsValue;
+
+    fn try_from(args: DisplayOptionsArgs) -> Result<Self, Self::Error> {
+        let mut options = DisplayOptions::default();
+        if let Some(base) = args.base {
+            let mut chars = base.chars();
+            let (Some(code), None) = (chars.next(), chars.next()) else {
+                return Err(JsValue::from_str(&format!(
+                    "Invalid multibase code: {base}"
+                )));
+            };
+            options.base = Base::from_code(code).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
+        if let Some(codec) = args.fingerprint_codec {
+            options.fingerprint_codec = parse_codec(&codec)?;
+        }
+        if let Some(include_bytes) = args.include_bytes {
+            options.include_bytes = include_bytes;
+        }
+        if let Some(include_key_material) = args.include_key_material {
+            options.include_key_material = include_key_material;
+        }
+        Ok(options)
+    }
+}
+
+/// Reads [DisplayOptions] from JavaScript, undefined or null gives the defaults
+fn display_options(options: JsValue) -> Result<DisplayOptions, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(DisplayOptions::default());
+    }
+    let args: DisplayOptionsArgs = serde_wasm_bindgen::from_value(options)?;
+    args.try_into()
+}
+
+/// Parses a multicodec name, such as "ed25519-priv"
+fn parse_codec(codec: &str) -> Result<Codec, JsValue> {
+    Codec::try_from(codec).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// The error thrown by a callback.
+///
+/// Callbacks report a [CryptoError] by throwing an object with its `code`, such as
+/// `{ code: "user-rejected", reason: "..." }`. The older `{ rejected: true, reason }`
+/// still means [CryptoError::UserRejected]. Anything else thrown means the signer is
+/// unavailable.
+fn callback_error(name: &str, e: JsValue) -> bestsign_core::Error {
+    let property = |p: &str| {
+        js_sys::Reflect::get(&e, &JsValue::from_str(p))
+            .ok()
+            .and_then(|v| v.as_string())
+    };
+    let rejected = js_sys::Reflect::get(&e, &JsValue::from_str("rejected"))
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+    if rejected {
+        return CryptoError::UserRejected(property("reason").unwrap_or_default()).into();
+    }
+    let message = e
+        .as_string()
+        .or_else(|| property("message"))
+        .unwrap_or("No Error message found".to_string());
+    tracing::error!("Error calling {name}: {message}");
+    property("code")
+        .and_then(|code| CryptoError::from_code(&code, property("reason").unwrap_or_default()))
+        .unwrap_or_else(|| {
+            CryptoError::SignerUnavailable(format!("Error calling {name}: {message}"))
+        })
+        .into()
+}
+
+/// Turns the error into a JS `Error`, with the `code` of its [CryptoError] and the
+/// `stage` that failed as properties, when there are any
+fn into_js_error(e: bestsign_core::Error) -> JsValue {
+    let js_error = js_sys::Error::new(&e.to_string());
+    let set = |name: &str, value: &str| {
+        let _ = js_sys::Reflect::set(
+            &js_error,
+            &JsValue::from_str(name),
+            &JsValue::from_str(value),
+        );
+    };
+    if let Some(crypto) = e.crypto() {
+        set("code", crypto.code());
+    }
+    match e {
+        bestsign_core::Error::KeyGeneration { .. } => set("stage", "key-generation"),
+        bestsign_core::Error::VladSigning(_) => set("stage", "vlad-signing"),
+        bestsign_core::Error::EntrySigning(_) => set("stage", "entry-signing"),
+        _ => {}
+    }
+    js_error.into()
 }
 
 /// Struct that will implement KeyManager
@@ -63,11 +177,11 @@ impl KeyHandler {
         self.key = Some(key);
     }
 
-    /// Get the /pubkey
-    pub fn get_key(&self, key: &Key) -> Result<Multikey, bestsign_core::Error> {
+    /// Get the secret key for the key-path, such as the /pubkey, with the given codec
+    pub fn get_key(&self, key: &Key, codec: Codec) -> Result<Multikey, bestsign_core::Error> {
         let key_args = KeyArgs {
             key: key.to_string(),
-            codec: Codec::Ed25519Priv.to_string(),
+            codec: codec.to_string(),
             threshold: 1,
             limit: 1,
         };
@@ -77,21 +191,10 @@ impl KeyHandler {
                 &JsValue::NULL,
                 &serde_wasm_bindgen::to_value(&key_args).unwrap(),
             )
-            .map_err(|e| {
-                tracing::error!(
-                    "Error calling get_key: {}",
-                    e.as_string()
-                        .unwrap_or("No Error message found".to_string())
-                );
-                bestsign_core::Error::Generic(format!(
-                    "Error calling get_key: {}",
-                    e.as_string()
-                        .unwrap_or("No Error message found".to_string())
-                ))
-            })?;
+            .map_err(|e| callback_error("get_key", e))?;
 
         let mk: Multikey = serde_wasm_bindgen::from_value(k).map_err(|e| {
-            bestsign_core::Error::Generic(format!("Error converting result to Multikey: {}", e))
+            CryptoError::SignerUnavailable(format!("Error converting result to Multikey: {}", e))
         })?;
 
         Ok(mk)
@@ -121,22 +224,14 @@ impl CryptoManager for KeyHandler {
         })?;
 
         // use apply to call the callback with the args
-        let result = self.get_key_callback.call1(&this, &args_js).map_err(|e| {
-            tracing::error!(
-                "Error calling get_key: {}",
-                e.as_string()
-                    .unwrap_or("No Error message found".to_string())
-            );
-            bestsign_core::Error::Generic(format!(
-                "Error calling get_key: {}",
-                e.as_string()
-                    .unwrap_or("No Error message found".to_string())
-            ))
-        })?;
+        let result = self
+            .get_key_callback
+            .call1(&this, &args_js)
+            .map_err(|e| callback_error("get_key", e))?;
 
         // convert the result to a Multikey
         let mk: Multikey = serde_wasm_bindgen::from_value(result).map_err(|e| {
-            bestsign_core::Error::Generic(format!("Error converting result to Multikey: {}", e))
+            CryptoError::SignerUnavailable(format!("Error converting result to Multikey: {}", e))
         })?;
 
         // if Key is "/pubkey" then set the key
@@ -148,28 +243,48 @@ impl CryptoManager for KeyHandler {
 
     /// Binds the prover to the given callback function
     fn prove(&self, mk: &Multikey, data: &[u8]) -> Result<Multisig, bestsign_core::Error> {
+        self.sign(mk, data, None)
+    }
+
+    /// Passes the context to the callback along with the data
+    fn prove_with_context(
+        &self,
+        mk: &Multikey,
+        data: &[u8],
+        context: &SigningContext,
+    ) -> Result<Multisig, bestsign_core::Error> {
+        self.sign(mk, data, Some(context.clone()))
+    }
+}
+
+impl KeyHandler {
+    /// Calls the sign callback
+    fn sign(
+        &self,
+        mk: &Multikey,
+        data: &[u8],
+        context: Option<SigningContext>,
+    ) -> Result<Multisig, bestsign_core::Error> {
         // use the callback to sign the data
         let this = JsValue::NULL;
 
         let args = SignArgs {
             mk: mk.clone(),
             data: data.to_vec(),
+            context,
         };
 
         let args_js = serde_wasm_bindgen::to_value(&args).map_err(|e| {
             bestsign_core::Error::Generic(format!("Error converting args to JsValue: {e}"))
         })?;
 
-        let result = self.sign_callback.call1(&this, &args_js).map_err(|e| {
-            bestsign_core::Error::Generic(format!(
-                "Error calling sign: {}",
-                e.as_string()
-                    .unwrap_or("No Error message found".to_string())
-            ))
-        })?;
+        let result = self
+            .sign_callback
+            .call1(&this, &args_js)
+            .map_err(|e| callback_error("sign", e))?;
 
         let sig: Multisig = serde_wasm_bindgen::from_value(result).map_err(|e| {
-            bestsign_core::Error::Generic(format!("Error converting result to Multisig: {}", e))
+            CryptoError::SignerUnavailable(format!("Error converting result to Multisig: {}", e))
         })?;
 
         Ok(sig)
@@ -213,6 +328,27 @@ impl ProvenanceLogBuilder {
         self.inner.entry_unlock_script = script;
     }
 
+    /// Set the codec name of the Vlad key, such as "secp256k1-priv"
+    #[wasm_bindgen]
+    pub fn set_vlad_key_codec(&mut self, codec: &str) -> Result<(), JsValue> {
+        self.inner.with_vlad_key_codec(parse_codec(codec)?);
+        Ok(())
+    }
+
+    /// Set the codec name of the entry key, such as "p256-priv"
+    #[wasm_bindgen]
+    pub fn set_entrykey_codec(&mut self, codec: &str) -> Result<(), JsValue> {
+        self.inner.with_entrykey_codec(parse_codec(codec)?);
+        Ok(())
+    }
+
+    /// Set the codec name of the pubkey, such as "bls12_381-g1-priv"
+    #[wasm_bindgen]
+    pub fn set_pubkey_codec(&mut self, codec: &str) -> Result<(), JsValue> {
+        self.inner.with_pubkey_codec(parse_codec(codec)?);
+        Ok(())
+    }
+
     /// Set additional Key params
     #[wasm_bindgen]
     pub fn add_key(&mut self, op: JsValue) -> Result<(), JsValue> {
@@ -258,17 +394,14 @@ impl ProvenanceLogBuilder {
 
         let log = create(&config, &mut self.key_manager).map_err(|e| {
             tracing::error!("Error creating log: {}", e);
-            JsValue::from_str(&e.to_string())
+            into_js_error(e)
         })?;
 
-        // serialize the log to serde_cbor and then to JsValue for return
-        let log_cbor = serde_cbor::to_vec(&log).map_err(|e| {
-            tracing::error!("Error serializing log to CBOR: {}", e);
-            JsValue::from_str(&e.to_string())
-        })?;
+        // serialize the log to an envelope and then to JsValue for return
+        let log_bytes = encode_log(&log);
 
         // use js_sys
-        let log_js = js_sys::Uint8Array::from(log_cbor.as_slice()).into();
+        let log_js = js_sys::Uint8Array::from(log_bytes.as_slice()).into();
 
         Ok(log_js)
     }
@@ -292,17 +425,20 @@ impl ProvenanceLog {
         get_key: &Function,
         prove: &Function,
     ) -> Result<ProvenanceLog, JsValue> {
-        // deserialize the log from CBOR
-        let log: Log = serde_cbor::from_slice(log)
+        // deserialize the log from an envelope, or a legacy CBOR blob
+        let log: Log = decode_log(log)
             .map_err(|e| JsValue::from_str(&format!("Error deserializing log: {}", e)))?;
 
         // start with Default Config, user can update it as desired
         let key_manager = KeyHandler::new(get_key, prove);
 
-        // setup /pubkey with Key from DEFAULT_PUBKEY
+        // setup /pubkey with Key from DEFAULT_PUBKEY, in the codec of the current /pubkey
+        let codec = key_codec(&log, DEFAULT_PUBKEY)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?
+            .unwrap_or(Codec::Ed25519Priv);
         let pubkey_rust = key_manager
-            .get_key(&Key::try_from(DEFAULT_PUBKEY).unwrap())
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            .get_key(&Key::try_from(DEFAULT_PUBKEY).unwrap(), codec)
+            .map_err(into_js_error)?;
 
         let config = UpdateConfig::new(Script::Code(Key::default(), unlock), pubkey_rust);
 
@@ -315,24 +451,92 @@ impl ProvenanceLog {
 
     /// Get a structured representation of the Plog for display
     #[wasm_bindgen]
-    pub fn display(&self) -> Result<JsValue, JsValue> {
-        let display_data =
-            get_display_data(&self.log).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    pub fn display(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = display_options(options)?;
+        let display_data = get_display_data_with(&self.log, &options)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
         serde_wasm_bindgen::to_value(&display_data)
             .map_err(|e| JsValue::from_str(&format!("Failed to serialize display data: {}", e)))
     }
 
+    /// Get the display records of every entry, from first to latest
+    #[wasm_bindgen]
+    pub fn timeline(&self, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = display_options(options)?;
+        let timeline = get_timeline_with(&self.log, &options)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        serde_wasm_bindgen::to_value(&timeline)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize timeline: {}", e)))
+    }
+
+    /// Verify the Plog and report on every entry
+    #[wasm_bindgen]
+    pub fn verification_report(&self) -> Result<JsValue, JsValue> {
+        let report = VerificationReport::new(&self.log);
+
+        serde_wasm_bindgen::to_value(&report)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize report: {}", e)))
+    }
+
+    /// Get the key-value state of the Plog right after the entry with the seqno
+    #[wasm_bindgen]
+    pub fn state_at(&self, seqno: u64) -> Result<JsValue, JsValue> {
+        let state =
+            state_at_seqno(&self.log, seqno).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        serde_wasm_bindgen::to_value(&state)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize state: {}", e)))
+    }
+
+    /// Get every change to the key-path, or under the branch, oldest first
+    #[wasm_bindgen]
+    pub fn key_history(&self, key: &str) -> Result<JsValue, JsValue> {
+        let history = key_history(&self.log, key).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        serde_wasm_bindgen::to_value(&history)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize key history: {}", e)))
+    }
+
+    /// Get what changed from this Plog to a newer serialized version of it
+    #[wasm_bindgen]
+    pub fn diff(&self, newer: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+        let options = display_options(options)?;
+        let newer: Log = decode_log(newer)
+            .map_err(|e| JsValue::from_str(&format!("Error deserializing log: {}", e)))?;
+
+        let diff = diff_logs_with(&self.log, &newer, &options)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        serde_wasm_bindgen::to_value(&diff)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize diff: {}", e)))
+    }
+
+    /// Get what changed between two seqnos of the Plog
+    #[wasm_bindgen]
+    pub fn diff_seqnos(&self, from: u64, to: u64, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = display_options(options)?;
+        let diff = diff_seqnos_with(&self.log, from, to, &options)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        serde_wasm_bindgen::to_value(&diff)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize diff: {}", e)))
+    }
+
+    /// Export the Plog as lossless DAG-JSON
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        log_to_json_string(&self.log).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     /// Serializ the Plog for export to JavaScript
     #[wasm_bindgen]
     pub fn serialize(&self) -> Result<JsValue, JsValue> {
-        let log_cbor = serde_cbor::to_vec(&self.log).map_err(|e| {
-            tracing::error!("Error serializing log to CBOR: {}", e);
-            JsValue::from_str(&e.to_string())
-        })?;
+        let log_bytes = encode_log(&self.log);
 
         // use js_sys
-        let log_js = js_sys::Uint8Array::from(log_cbor.as_slice()).into();
+        let log_js = js_sys::Uint8Array::from(log_bytes.as_slice()).into();
 
         Ok(log_js)
     }
@@ -375,8 +579,7 @@ impl ProvenanceLog {
 
         tracing::info!("Config set");
 
-        update_plog(&mut self.log, &config, &mut self.key_manager)
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        update_plog(&mut self.log, &config, &mut self.key_manager).map_err(into_js_error)?;
 
         Ok(())
     }
@@ -393,12 +596,61 @@ pub fn decode_vlad(s: &str) -> Result<JsValue, JsValue> {
 
 /// Deserialize the Plog into a human readable format. Same as display(), but no auth needed
 #[wasm_bindgen]
-pub fn deserialize_plog(log: &[u8]) -> Result<JsValue, JsValue> {
-    let log: Log = serde_cbor::from_slice(log)
+pub fn deserialize_plog(log: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+    let options = display_options(options)?;
+    let log: Log = decode_log(log)
         .map_err(|e| JsValue::from_str(&format!("Error deserializing log: {}", e)))?;
 
-    let display_data = get_display_data(&log).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let display_data =
+        get_display_data_with(&log, &options).map_err(|e| JsValue::from_str(&e.to_string()))?;
 
     serde_wasm_bindgen::to_value(&display_data)
         .map_err(|e| JsValue::from_str(&format!("Failed to serialize display data: {}", e)))
 }
+
+/// Verify the Plog and report on every entry, no auth needed
+#[wasm_bindgen]
+pub fn verify_plog(log: &[u8]) -> Result<JsValue, JsValue> {
+    let log: Log = decode_log(log)
+        .map_err(|e| JsValue::from_str(&format!("Error deserializing log: {}", e)))?;
+
+    let report = VerificationReport::new(&log);
+
+    serde_wasm_bindgen::to_value(&report)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize report: {}", e)))
+}
+
+/// Get what changed between two serialized versions of a Plog, no auth needed
+#[wasm_bindgen]
+pub fn diff_plogs(old: &[u8], new: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+    let options = display_options(options)?;
+    let old: Log = decode_log(old)
+        .map_err(|e| JsValue::from_str(&format!("Error deserializing log: {}", e)))?;
+    let new: Log = decode_log(new)
+        .map_err(|e| JsValue::from_str(&format!("Error deserializing log: {}", e)))?;
+
+    let diff =
+        diff_logs_with(&old, &new, &options).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    serde_wasm_bindgen::to_value(&diff)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize diff: {}", e)))
+}
+
+/// Convert a serialized Plog to lossless DAG-JSON, no auth needed
+#[wasm_bindgen]
+pub fn plog_to_json(log: &[u8]) -> Result<String, JsValue> {
+    let log: Log = decode_log(log)
+        .map_err(|e| JsValue::from_str(&format!("Error deserializing log: {}", e)))?;
+
+    log_to_json_string(&log).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Import a Plog from DAG-JSON and serialize it, no auth needed
+#[wasm_bindgen]
+pub fn plog_from_json(json: &str) -> Result<JsValue, JsValue> {
+    let log = log_from_json_str(json)
+        .map_err(|e| JsValue::from_str(&format!("Error importing log: {}", e)))?;
+
+    let log_bytes = encode_log(&log);
+    Ok(js_sys::Uint8Array::from(log_bytes.as_slice()).into())
+}
@@ -0,0 +1,143 @@
+//! Verified checkpoints of plog state.
+//!
+//! Verifying a plog runs the lock and unlock scripts of every entry from the foot,
+//! so doing it again before every update makes long logs expensive. A [Checkpoint]
+//! records the state after a verified entry: its seqno, its [Cid] and a snapshot of
+//! the key-value pairs. Checkpoints can be persisted and handed to
+//! [update_plog_from](crate::ops::update_plog_from), which appends to the log
+//! without verifying its history again.
+//!
+//! Verifying only the entries added after a checkpoint needs a verifier that can
+//! start from a given state, which provenance-log does not offer yet, so
+//! [Checkpoint::resume] still verifies a grown log from the foot.
+
+use provenance_log::multicid;
+
+use multicid::{Cid, Vlad};
+use provenance_log::{Entry, Key, Log, LogValue, Op};
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{CheckpointError, PlogError},
+    Error,
+};
+
+/// The verified state of a plog after one of its entries
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    /// The plog the checkpoint is for
+    pub vlad: Vlad,
+    /// The seqno of the checkpointed entry
+    pub seqno: u64,
+    /// The [Cid] of the checkpointed entry
+    pub cid: Cid,
+    /// The key-value pairs after the checkpointed entry, keyed by key-path
+    pub state: BTreeMap<String, LogValue>,
+}
+
+impl Checkpoint {
+    /// Records the state after the [Entry], from the pairs the verifier returned for it
+    pub fn new<'a>(
+        vlad: &Vlad,
+        entry: &Entry,
+        pairs: impl IntoIterator<Item = (&'a Key, &'a LogValue)>,
+    ) -> Self {
+        Self {
+            vlad: vlad.clone(),
+            seqno: entry.seqno(),
+            cid: entry.cid(),
+            state: pairs
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Verifies the whole [Log] and returns the [Checkpoint] of its head
+    pub fn from_log(log: &Log) -> Result<Self, Error> {
+        let mut latest = None;
+        for ret in log.verify() {
+            let (_, entry, kvp) = ret?;
+            latest = Some(Self::new(&log.vlad, &entry, kvp.iter()));
+        }
+        latest.ok_or_else(|| PlogError::NoFirstEntry.into())
+    }
+
+    /// The value at the key-path
+    pub fn get(&self, key: &str) -> Option<&LogValue> {
+        self.state.get(key)
+    }
+
+    /// Returns the [Checkpoint] of the head of the [Log], starting from this one.
+    ///
+    /// The checkpointed entry must be on the chain from the head of the log back to
+    /// its foot. If the log has not grown since the checkpoint, nothing is verified.
+    /// Otherwise the whole log is verified from the foot, and the snapshot is checked
+    /// against the verified state at the checkpoint seqno: this is not incremental, it
+    /// only saves the caller from tracking whether the log grew. Either way the
+    /// returned checkpoint can be used to append without further verification.
+    pub fn resume(&self, log: &Log) -> Result<Self, Error> {
+        if self.vlad != log.vlad {
+            return Err(CheckpointError::VladMismatch.into());
+        }
+        if !self.is_on_head_chain(log) {
+            return Err(CheckpointError::NotInLog(self.seqno).into());
+        }
+        if self.cid == log.head {
+            return Ok(self.clone());
+        }
+
+        let mut latest = None;
+        for ret in log.verify() {
+            let (_, entry, kvp) = ret?;
+            let checkpoint = Self::new(&log.vlad, &entry, kvp.iter());
+            if checkpoint.seqno == self.seqno && checkpoint != *self {
+                return Err(CheckpointError::StateMismatch(self.seqno).into());
+            }
+            latest = Some(checkpoint);
+        }
+        latest.ok_or_else(|| PlogError::NoFirstEntry.into())
+    }
+
+    /// Whether the checkpointed entry is the head of the [Log] or one of its ancestors
+    fn is_on_head_chain(&self, log: &Log) -> bool {
+        let mut cid = log.head.clone();
+        while let Some(entry) = log.entries.get(&cid) {
+            if entry.seqno() <= self.seqno {
+                return cid == self.cid && entry.seqno() == self.seqno;
+            }
+            cid = entry.prev();
+        }
+        false
+    }
+
+    /// The [Checkpoint] after applying the ops of the next [Entry] to the snapshot.
+    ///
+    /// The entry is not verified, so this is only for entries the caller just built
+    /// and signed on top of this checkpoint.
+    pub(crate) fn advance(&self, entry: &Entry) -> Self {
+        let mut state = self.state.clone();
+        for op in entry.ops() {
+            match op {
+                Op::Update(key, value) => {
+                    state.insert(key.to_string(), value.clone());
+                }
+                // deleting a branch deletes everything under it, as the verifier does
+                Op::Delete(key) if key.as_str().ends_with('/') => {
+                    state.retain(|path, _| !path.starts_with(key.as_str()));
+                }
+                Op::Delete(key) => {
+                    state.remove(&key.to_string());
+                }
+                Op::Noop(_) => {}
+            }
+        }
+        Self {
+            vlad: self.vlad.clone(),
+            seqno: entry.seqno(),
+            cid: entry.cid(),
+            state,
+        }
+    }
+}
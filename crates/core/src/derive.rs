@@ -0,0 +1,138 @@
+//! Deterministic key derivation per key-path.
+//!
+//! Every key is derived from the wallet seed, the key-path it is for, the key
+//! codec and a rotation counter, so each key-path and each rotation of it gets its
+//! own key, and the same inputs always give the same key.
+//!
+//! The scheme, version 1:
+//!
+//! 1. Build the input: the seed, the key-path string, the codec as its multicodec
+//!    varint, each of them length prefixed with an unsigned varint, then the
+//!    rotation counter as an unsigned varint.
+//! 2. Hash the input with BLAKE3 in key derivation mode, using [DERIVATION_CONTEXT]
+//!    as the context string, into a 32 byte key seed.
+//! 3. Build the [Multikey] with `mk::Builder::new_from_seed(codec, key_seed)`.
+//!
+//! The rotation counter starts at 0 for the first key of a key-path and goes up by
+//! one for every rotation. To recover keys from a seed alone, [recover_key] tries
+//! the rotations in order until it finds the public key stored in the plog.
+//!
+//! [SeededKeyManager] is a [CryptoManager] using the scheme, so creating and updating
+//! a plog with the same seed and inputs gives byte-identical logs, as long as the key
+//! codecs sign deterministically, as Ed25519 does.
+
+use provenance_log::{multicodec, multikey, multisig, multitrait, multiutil};
+
+use multicodec::Codec;
+use multikey::{mk, Multikey, Views as _};
+use multisig::Multisig;
+use multitrait::EncodeInto;
+use multiutil::{CodecInfo, Varbytes, Varuint};
+use provenance_log::Key;
+use std::collections::BTreeMap;
+
+use crate::{ops::CryptoManager, utils::secret_codec, Error};
+
+/// The BLAKE3 context string of the derivation scheme
+pub const DERIVATION_CONTEXT: &str = "bestsign 2025-01-01 key-path derivation v1";
+
+/// Derives the 32 byte key seed for the key-path, codec and rotation
+pub fn derive_seed(seed: &[u8], key_path: &Key, codec: Codec, rotation: u64) -> [u8; 32] {
+    let mut input = Varbytes(seed.to_vec()).encode_into();
+    input.append(&mut Varbytes(key_path.as_str().as_bytes().to_vec()).encode_into());
+    input.append(&mut Varbytes(codec.into()).encode_into());
+    input.append(&mut Varuint(rotation).encode_into());
+    blake3::derive_key(DERIVATION_CONTEXT, &input)
+}
+
+/// Derives the secret [Multikey] for the key-path, codec and rotation
+pub fn derive_key(
+    seed: &[u8],
+    key_path: &Key,
+    codec: Codec,
+    rotation: u64,
+) -> Result<Multikey, Error> {
+    let key_seed = derive_seed(seed, key_path, codec, rotation);
+    Ok(mk::Builder::new_from_seed(codec, &key_seed)?.try_build()?)
+}
+
+/// Finds the rotation of the key-path whose key has the public key, trying the
+/// rotations from 0 up to `max_rotation`
+///
+/// Returns the rotation and the secret key.
+pub fn recover_key(
+    seed: &[u8],
+    key_path: &Key,
+    public_key: &Multikey,
+    max_rotation: u64,
+) -> Result<Option<(u64, Multikey)>, Error> {
+    let codec = secret_codec(public_key.codec());
+    for rotation in 0..=max_rotation {
+        let mk = derive_key(seed, key_path, codec, rotation)?;
+        if mk.conv_view()?.to_public_key()? == *public_key {
+            return Ok(Some((rotation, mk)));
+        }
+    }
+    Ok(None)
+}
+
+/// A [CryptoManager] deriving every key from a seed
+///
+/// The first key requested for a key-path is rotation 0, and each further request
+/// for the same key-path derives the next rotation.
+#[derive(Clone, Debug)]
+pub struct SeededKeyManager {
+    seed: Vec<u8>,
+    /// The next rotation of each key-path
+    rotations: BTreeMap<String, u64>,
+    /// The latest key of each key-path
+    keys: BTreeMap<String, Multikey>,
+}
+
+impl SeededKeyManager {
+    /// A key manager for the seed, with no keys derived yet
+    pub fn new(seed: impl Into<Vec<u8>>) -> Self {
+        Self {
+            seed: seed.into(),
+            rotations: BTreeMap::new(),
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the next rotation of the key-path, to carry on with a plog whose keys
+    /// were rotated before
+    pub fn with_rotation(mut self, key_path: &str, rotation: u64) -> Self {
+        self.rotations.insert(key_path.to_string(), rotation);
+        self
+    }
+
+    /// The latest key derived for the key-path
+    pub fn key(&self, key_path: &str) -> Option<&Multikey> {
+        self.keys.get(key_path)
+    }
+
+    /// The rotation the next key derived for the key-path will have
+    pub fn next_rotation(&self, key_path: &str) -> u64 {
+        self.rotations.get(key_path).copied().unwrap_or_default()
+    }
+}
+
+impl CryptoManager for SeededKeyManager {
+    fn get_mk(
+        &mut self,
+        key: &Key,
+        codec: Codec,
+        _threshold: usize,
+        _limit: usize,
+    ) -> Result<Multikey, Error> {
+        let rotation = self.next_rotation(key.as_str());
+        let mk = derive_key(&self.seed, key, codec, rotation)?;
+        self.rotations.insert(key.to_string(), rotation + 1);
+        self.keys.insert(key.to_string(), mk.clone());
+        Ok(mk)
+    }
+
+    fn prove(&self, mk: &Multikey, data: &[u8]) -> Result<Multisig, Error> {
+        Ok(mk.sign_view()?.sign(data, false, None)?)
+    }
+}
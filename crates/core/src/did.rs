@@ -0,0 +1,300 @@
+//! `did:plog` DIDs and DID Documents.
+//!
+//! The DID of a plog is `did:plog:` followed by its base36 multibase encoded [Vlad],
+//! so it never changes while the keys in the plog rotate. Only this canonical form is
+//! accepted, so every plog has exactly one DID. The DID Document is built from
+//! the verified state at the head of the plog:
+//!
+//! - every [Multikey] in the state is a verification method, with the key-path as
+//!   its fragment, such as `did:plog:k...#pubkey`, except the one-time entry and
+//!   Vlad keys. The segments of the key-path are joined with dashes, and any dash
+//!   or other character outside the URI unreserved set within a segment is percent
+//!   encoded, so `/a/b` is `#a-b` and `/a-b` is `#a%2Db`
+//! - `/pubkey` is used for authentication and assertions, and both `/pubkey` and
+//!   `/recoverykey` can invoke capabilities, since either may sign the next entry
+//! - each `/service/<id>/endpoint` string is a service, of the type in
+//!   `/service/<id>/type`, or [DEFAULT_SERVICE_TYPE]. Its fragment is made from the
+//!   endpoint key-path the same way, such as `#service-hub-endpoint`, so it never
+//!   equals the fragment of a key
+//!
+//! Keys are given as `publicKeyMultibase`, the multibase encoded [Multikey].
+//!
+//! [resolve_did] finds the head with a [HeadResolver], resolves and verifies the
+//! plog with a [Resolver], and returns the document along with the seqno and
+//! [Cid] of the head it was built from.
+
+use provenance_log::{multibase, multicid, multikey};
+
+use multibase::Base;
+use multicid::{Cid, EncodedCid, EncodedVlad, Vlad};
+use multikey::{EncodedMultikey, Multikey};
+use provenance_log::{Log, LogValue};
+
+use crate::{
+    checkpoint::Checkpoint,
+    error::DidError,
+    ops::config::defaults::{DEFAULT_ENTRYKEY, DEFAULT_PUBKEY, DEFAULT_VLAD_KEY},
+    resolve::{resolve_by_vlad, HeadResolver, Resolver},
+    Error,
+};
+
+/// The prefix of every plog DID
+pub const DID_PREFIX: &str = "did:plog:";
+
+/// The JSON-LD context of the DID Document
+pub const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+
+/// The key-path of the recovery key
+pub const RECOVERY_KEY: &str = "/recoverykey";
+
+/// The branch holding the service endpoints
+pub const SERVICE_BRANCH: &str = "/service/";
+
+/// The type of a service without a `/service/<id>/type`
+pub const DEFAULT_SERVICE_TYPE: &str = "LinkedDomains";
+
+/// The verification method type of the keys
+const VERIFICATION_METHOD_TYPE: &str = "Multikey";
+
+/// The multibase of the Vlad in the DID, the same one used for display
+const DID_BASE: Base = Base::Base36Lower;
+
+/// The multibase of the keys and the head [Cid]
+const KEY_BASE: Base = Base::Base58Btc;
+
+/// The DID of the plog with the [Vlad]
+pub fn did_from_vlad(vlad: &Vlad) -> String {
+    format!("{DID_PREFIX}{}", EncodedVlad::new(DID_BASE, vlad.clone()))
+}
+
+/// The [Vlad] of a plog DID, which must be in the canonical form of [did_from_vlad]
+pub fn vlad_from_did(did: &str) -> Result<Vlad, Error> {
+    let encoded = did
+        .strip_prefix(DID_PREFIX)
+        .ok_or_else(|| DidError::InvalidDid(did.to_string()))?;
+    let vlad = EncodedVlad::try_from(encoded)
+        .map_err(|_| DidError::InvalidDid(did.to_string()))?
+        .to_inner();
+    if did_from_vlad(&vlad) != did {
+        return Err(DidError::InvalidDid(did.to_string()).into());
+    }
+    Ok(vlad)
+}
+
+/// A key of the DID subject
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct VerificationMethod {
+    /// The DID URL of the key, the DID with the key-path as fragment
+    pub id: String,
+    /// The type of the key
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub method_type: String,
+    /// The DID of the plog
+    pub controller: String,
+    /// The multibase encoded public [Multikey]
+    pub public_key_multibase: String,
+}
+
+/// A service endpoint of the DID subject
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Service {
+    /// The DID URL of the service, the DID with its endpoint key-path as fragment
+    pub id: String,
+    /// The type of the service
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub service_type: String,
+    /// Where the service is
+    pub service_endpoint: String,
+}
+
+/// The DID Document of a plog
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct DidDocument {
+    /// The JSON-LD contexts
+    #[cfg_attr(feature = "serde", serde(rename = "@context"))]
+    pub context: Vec<String>,
+    /// The DID
+    pub id: String,
+    /// The keys, by key-path
+    pub verification_method: Vec<VerificationMethod>,
+    /// The ids of the keys that authenticate the subject
+    pub authentication: Vec<String>,
+    /// The ids of the keys that make assertions for the subject
+    pub assertion_method: Vec<String>,
+    /// The ids of the keys that can update the plog
+    pub capability_invocation: Vec<String>,
+    /// The services
+    pub service: Vec<Service>,
+}
+
+impl DidDocument {
+    /// Builds the DID Document from the state of the [Checkpoint]
+    pub fn from_checkpoint(checkpoint: &Checkpoint) -> Self {
+        let did = did_from_vlad(&checkpoint.vlad);
+
+        let mut verification_method = Vec::new();
+        for (key_path, value) in &checkpoint.state {
+            if key_path == DEFAULT_ENTRYKEY || key_path == DEFAULT_VLAD_KEY {
+                continue;
+            }
+            let LogValue::Data(data) = value else {
+                continue;
+            };
+            let Ok(mk) = Multikey::try_from(data.as_slice()) else {
+                continue;
+            };
+            verification_method.push(VerificationMethod {
+                id: key_path_id(&did, key_path),
+                method_type: VERIFICATION_METHOD_TYPE.to_string(),
+                controller: did.clone(),
+                public_key_multibase: EncodedMultikey::new(KEY_BASE, mk).to_string(),
+            });
+        }
+
+        let relationship = |key_paths: &[&str]| -> Vec<String> {
+            key_paths
+                .iter()
+                .map(|key_path| key_path_id(&did, key_path))
+                .filter(|id| verification_method.iter().any(|method| method.id == *id))
+                .collect()
+        };
+        let authentication = relationship(&[DEFAULT_PUBKEY]);
+        let assertion_method = relationship(&[DEFAULT_PUBKEY]);
+        let capability_invocation = relationship(&[DEFAULT_PUBKEY, RECOVERY_KEY]);
+
+        let service = checkpoint
+            .state
+            .iter()
+            .filter_map(|(key_path, value)| {
+                let id = key_path
+                    .strip_prefix(SERVICE_BRANCH)?
+                    .strip_suffix("/endpoint")?;
+                let LogValue::Str(endpoint) = value else {
+                    return None;
+                };
+                let service_type = match checkpoint.get(&format!("{SERVICE_BRANCH}{id}/type")) {
+                    Some(LogValue::Str(service_type)) => service_type.clone(),
+                    _ => DEFAULT_SERVICE_TYPE.to_string(),
+                };
+                Some(Service {
+                    id: key_path_id(&did, key_path),
+                    service_type,
+                    service_endpoint: endpoint.clone(),
+                })
+            })
+            .collect();
+
+        Self {
+            context: vec![DID_CONTEXT.to_string()],
+            id: did,
+            verification_method,
+            authentication,
+            assertion_method,
+            capability_invocation,
+            service,
+        }
+    }
+}
+
+/// The DID Document metadata, describing the head the document was built from
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct DidDocumentMetadata {
+    /// The seqno of the head entry
+    pub version_id: u64,
+    /// The multibase encoded [Cid] of the head entry
+    pub updated: String,
+}
+
+/// A DID Document along with its metadata
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct ResolvedDid {
+    /// The DID Document
+    pub did_document: DidDocument,
+    /// The metadata of the document
+    pub did_document_metadata: DidDocumentMetadata,
+}
+
+impl ResolvedDid {
+    /// Verifies the [Log] and builds the DID Document of its head
+    pub fn from_log(log: &Log) -> Result<Self, Error> {
+        Ok(Self::from_checkpoint(&Checkpoint::from_log(log)?))
+    }
+
+    /// Builds the DID Document of the verified state of the [Checkpoint]
+    pub fn from_checkpoint(checkpoint: &Checkpoint) -> Self {
+        Self {
+            did_document: DidDocument::from_checkpoint(checkpoint),
+            did_document_metadata: DidDocumentMetadata {
+                version_id: checkpoint.seqno,
+                updated: encoded_cid(&checkpoint.cid),
+            },
+        }
+    }
+}
+
+/// Resolves the DID: looks up the head of the plog with the [HeadResolver], then
+/// resolves and verifies the plog with the [Resolver] and builds its DID Document
+pub async fn resolve_did(
+    did: &str,
+    heads: &impl HeadResolver,
+    resolver: impl Resolver + Clone,
+) -> Result<ResolvedDid, Error> {
+    let vlad = vlad_from_did(did)?;
+    let resolved = resolve_by_vlad(&vlad, heads, resolver)
+        .await
+        .map_err(|e| DidError::Unresolved(e.to_string()))?;
+    ResolvedDid::from_log(&resolved.log)
+}
+
+/// The DID URL of the key-path, with the key-path as fragment, without the leading
+/// and trailing slashes and with the escaped segments joined by dashes
+fn key_path_id(did: &str, key_path: &str) -> String {
+    let fragment: Vec<String> = key_path
+        .trim_matches('/')
+        .split('/')
+        .map(escape_segment)
+        .collect();
+    format!("{did}#{}", fragment.join("-"))
+}
+
+/// Percent encodes every byte of the key-path segment but the unreserved letters,
+/// digits, `.`, `_` and `~`, so a dash in a segment is never a slash
+fn escape_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// The head [Cid] as given in the metadata
+fn encoded_cid(cid: &Cid) -> String {
+    EncodedCid::new(KEY_BASE, cid.clone()).to_string()
+}
@@ -0,0 +1,206 @@
+//! Differences between two states of a plog.
+//!
+//! Compares the verified state of two [Log]s with the same [Vlad](crate::Vlad),
+//! or of two seqnos of one [Log], and reports the key-paths that were added, removed
+//! or changed with their typed [DisplayData] values, the lock scripts that changed
+//! and the entries that are new.
+
+use provenance_log::{Entry, Key, Log, LogValue, Script};
+use std::collections::BTreeMap;
+
+use crate::{
+    checkpoint::Checkpoint,
+    error::HistoryError,
+    history::states,
+    utils::{display_value_with, DisplayData, DisplayOptions, EntryDisplay},
+    Error,
+};
+
+/// A key-path whose value changed
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangedValue {
+    /// The value in the older state
+    pub old: DisplayData,
+    /// The value in the newer state
+    pub new: DisplayData,
+}
+
+/// What changed between two states of a plog
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateDiff {
+    /// The seqno of the older state
+    pub from_seqno: u64,
+    /// The seqno of the newer state
+    pub to_seqno: u64,
+    /// Key-paths only in the newer state
+    pub added: Vec<DisplayData>,
+    /// Key-paths only in the older state
+    pub removed: Vec<DisplayData>,
+    /// Key-paths with a different value in the newer state
+    pub changed: Vec<ChangedValue>,
+    /// Key-paths of lock scripts only in the newer state
+    pub added_locks: Vec<Key>,
+    /// Key-paths of lock scripts only in the older state
+    pub removed_locks: Vec<Key>,
+    /// Key-paths of lock scripts that differ between the states
+    pub changed_locks: Vec<Key>,
+    /// The entries in the newer state that are not in the older one, in seqno order
+    pub new_entries: Vec<EntryDisplay>,
+}
+
+impl StateDiff {
+    /// Returns true if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.added_locks.is_empty()
+            && self.removed_locks.is_empty()
+            && self.changed_locks.is_empty()
+            && self.new_entries.is_empty()
+    }
+}
+
+/// Verifies both [Log]s and reports what changed from `old` to `new`
+pub fn diff_logs(old: &Log, new: &Log) -> Result<StateDiff, Error> {
+    diff_logs_with(old, new, &DisplayOptions::default())
+}
+
+/// Like [diff_logs], encoding values with the [DisplayOptions]
+pub fn diff_logs_with(old: &Log, new: &Log, options: &DisplayOptions) -> Result<StateDiff, Error> {
+    if old.vlad != new.vlad {
+        return Err(HistoryError::VladMismatch.into());
+    }
+    let from = Checkpoint::from_log(old)?;
+    let to = Checkpoint::from_log(new)?;
+
+    let new_entries = new
+        .entries
+        .iter()
+        .filter(|(cid, _)| !old.entries.contains_key(*cid))
+        .map(|(_, entry)| entry);
+
+    diff(
+        &from,
+        &old.entries[&from.cid],
+        &to,
+        &new.entries[&to.cid],
+        new_entries,
+        options,
+    )
+}
+
+/// Verifies the [Log] and reports what changed from seqno `from` to seqno `to`
+pub fn diff_seqnos(log: &Log, from: u64, to: u64) -> Result<StateDiff, Error> {
+    diff_seqnos_with(log, from, to, &DisplayOptions::default())
+}
+
+/// Like [diff_seqnos], encoding values with the [DisplayOptions]
+pub fn diff_seqnos_with(
+    log: &Log,
+    from: u64,
+    to: u64,
+    options: &DisplayOptions,
+) -> Result<StateDiff, Error> {
+    let all = states(log)?;
+    let find = |seqno: u64| {
+        all.iter()
+            .find(|state| state.seqno == seqno)
+            .ok_or(HistoryError::NoSuchSeqno(seqno))
+    };
+    let (from_state, to_state) = (find(from)?, find(to)?);
+
+    let new_entries = log
+        .entries
+        .values()
+        .filter(|entry| entry.seqno() > from && entry.seqno() <= to);
+
+    diff(
+        from_state,
+        &log.entries[&from_state.cid],
+        to_state,
+        &log.entries[&to_state.cid],
+        new_entries,
+        options,
+    )
+}
+
+fn diff<'a>(
+    from: &Checkpoint,
+    from_entry: &Entry,
+    to: &Checkpoint,
+    to_entry: &Entry,
+    new_entries: impl Iterator<Item = &'a Entry>,
+    options: &DisplayOptions,
+) -> Result<StateDiff, Error> {
+    let display = |key: &str, value: &LogValue| -> Result<DisplayData, Error> {
+        display_value_with(&Key::try_from(key)?, value, options)
+    };
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for (key, value) in &to.state {
+        match from.state.get(key) {
+            None => added.push(display(key, value)?),
+            Some(old) if old != value => changed.push(ChangedValue {
+                old: display(key, old)?,
+                new: display(key, value)?,
+            }),
+            Some(_) => {}
+        }
+    }
+    let removed = from
+        .state
+        .iter()
+        .filter(|(key, _)| !to.state.contains_key(*key))
+        .map(|(key, value)| display(key, value))
+        .collect::<Result<Vec<DisplayData>, Error>>()?;
+
+    let old_locks = locks_by_path(from_entry);
+    let new_locks = locks_by_path(to_entry);
+    let lock_key = |path: &String| Key::try_from(path.as_str());
+    let added_locks = new_locks
+        .keys()
+        .filter(|path| !old_locks.contains_key(*path))
+        .map(lock_key)
+        .collect::<Result<Vec<Key>, _>>()?;
+    let removed_locks = old_locks
+        .keys()
+        .filter(|path| !new_locks.contains_key(*path))
+        .map(lock_key)
+        .collect::<Result<Vec<Key>, _>>()?;
+    let changed_locks = new_locks
+        .iter()
+        .filter(|(path, script)| matches!(old_locks.get(*path), Some(old) if old != *script))
+        .map(|(path, _)| lock_key(path))
+        .collect::<Result<Vec<Key>, _>>()?;
+
+    let mut new_entries: Vec<&Entry> = new_entries.collect();
+    new_entries.sort_by_key(|entry| entry.seqno());
+    let new_entries = new_entries
+        .into_iter()
+        .map(|entry| EntryDisplay::new_with(entry, options))
+        .collect::<Result<Vec<EntryDisplay>, Error>>()?;
+
+    Ok(StateDiff {
+        from_seqno: from.seqno,
+        to_seqno: to.seqno,
+        added,
+        removed,
+        changed,
+        added_locks,
+        removed_locks,
+        changed_locks,
+        new_entries,
+    })
+}
+
+/// The lock scripts of the [Entry] by key-path
+fn locks_by_path(entry: &Entry) -> BTreeMap<String, Script> {
+    entry
+        .locks()
+        .map(|lock| (lock.path().to_string(), lock.clone()))
+        .collect()
+}
@@ -0,0 +1,222 @@
+//! Detached document signatures anchored to a plog.
+//!
+//! A [DetachedSignature] is made with the key stored at a key-path of a plog, such
+//! as `/pubkey`, at one of its entries. It carries the [Vlad], the [Cid] of that
+//! entry, the key-path, the [Multihash] of the document and the [Multisig], but not
+//! the document itself.
+//!
+//! The signature is over domain separated bytes: [DOCUMENT_CONTEXT], then the
+//! Vlad, the entry Cid, the key-path and the document multihash, each of them
+//! length prefixed with an unsigned varint. So a document signature can never
+//! be mistaken for an entry or Vlad signature made with the same key.
+//!
+//! A verifier resolves the plog up to the entry, checks it, and checks the
+//! signature against the key the key-path held right after that entry. Later
+//! rotations of the key do not invalidate the signature.
+
+use provenance_log::{multicid, multicodec, multihash, multikey, multisig, multitrait, multiutil};
+
+use multicid::{Cid, Vlad};
+use multicodec::Codec;
+use multihash::{mh, Multihash};
+use multikey::{Multikey, Views as _};
+use multisig::Multisig;
+use multitrait::{EncodeInto, TryDecodeFrom};
+use multiutil::{CodecInfo, Varbytes, Varuint};
+use provenance_log::{Key, Log, LogValue};
+
+use crate::{
+    error::{DocumentError, PlogError},
+    history::state_at_cid,
+    ops::{CryptoManager, SigningContext, SigningPurpose},
+    resolve::{resolve_plog, Resolver},
+    Error,
+};
+
+/// The domain separation prefix of the signed bytes
+pub const DOCUMENT_CONTEXT: &[u8] = b"bestsign detached document signature v1";
+
+/// The hash of the document, unless another is given
+pub const DEFAULT_DOCUMENT_HASH: Codec = Codec::Sha2256;
+
+/// The version of the binary encoding of [DetachedSignature]
+const DETACHED_SIGNATURE_VERSION: u64 = 1;
+
+/// A signature over a document with a key of a plog, detached from the document
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetachedSignature {
+    /// The plog of the signer
+    pub vlad: Vlad,
+    /// The [Cid] of the entry the signing key was valid at, the head when signing
+    pub head: Cid,
+    /// The key-path of the signing key
+    pub key_path: Key,
+    /// The hash of the document
+    pub hash: Multihash,
+    /// The signature over the [signed bytes](Self::signed_bytes)
+    pub signature: Multisig,
+}
+
+impl DetachedSignature {
+    /// Signs the document with the key at the key-path, as of the head of the [Log]
+    ///
+    /// The [Log] is verified, and the public half of `signing_key` must be the key
+    /// stored at the key-path. The key manager signs with the
+    /// [SigningPurpose::Document] context.
+    pub fn sign(
+        log: &Log,
+        key_path: &Key,
+        signing_key: &Multikey,
+        document: &[u8],
+        key_manager: &impl CryptoManager,
+    ) -> Result<Self, Error> {
+        Self::sign_with_hash(
+            log,
+            key_path,
+            signing_key,
+            document,
+            DEFAULT_DOCUMENT_HASH,
+            key_manager,
+        )
+    }
+
+    /// Like [sign](Self::sign), hashing the document with the codec
+    pub fn sign_with_hash(
+        log: &Log,
+        key_path: &Key,
+        signing_key: &Multikey,
+        document: &[u8],
+        hash: Codec,
+        key_manager: &impl CryptoManager,
+    ) -> Result<Self, Error> {
+        let state = state_at_cid(log, &log.head)?;
+        let public_key = signing_key.conv_view()?.to_public_key()?;
+        if stored_key(&state.state, key_path)? != public_key {
+            return Err(DocumentError::KeyMismatch(key_path.to_string()).into());
+        }
+
+        let hash = mh::Builder::new_from_bytes(hash, document)?.try_build()?;
+        let bytes = signed_bytes(&log.vlad, &log.head, key_path, &hash);
+        let context = SigningContext {
+            purpose: SigningPurpose::Document,
+            vlad: Some(log.vlad.clone()),
+            seqno: Some(state.seqno),
+            key_path: Some(key_path.clone()),
+            ops: Vec::new(),
+        };
+        let signature = key_manager.prove_with_context(signing_key, &bytes, &context)?;
+
+        Ok(Self {
+            vlad: log.vlad.clone(),
+            head: log.head.clone(),
+            key_path: key_path.clone(),
+            hash,
+            signature,
+        })
+    }
+
+    /// The bytes the signature is over
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        signed_bytes(&self.vlad, &self.head, &self.key_path, &self.hash)
+    }
+
+    /// Checks the signature over the document against a [Log] holding the signing
+    /// entry, and returns the seqno of the entry
+    ///
+    /// The log is verified up to the signing entry.
+    pub fn verify_with_log(&self, log: &Log, document: &[u8]) -> Result<u64, Error> {
+        if log.vlad != self.vlad {
+            return Err(DocumentError::VladMismatch.into());
+        }
+        let hash = mh::Builder::new_from_bytes(self.hash.codec(), document)?.try_build()?;
+        if hash != self.hash {
+            return Err(DocumentError::HashMismatch.into());
+        }
+
+        let state = state_at_cid(log, &self.head)?;
+        stored_key(&state.state, &self.key_path)?
+            .verify_view()?
+            .verify(&self.signature, Some(&self.signed_bytes()))
+            .map_err(|_| DocumentError::InvalidSignature)?;
+        Ok(state.seqno)
+    }
+
+    /// Resolves the plog up to the signing entry and checks the signature over the
+    /// document, like [verify_with_log](Self::verify_with_log)
+    pub async fn verify(
+        &self,
+        document: &[u8],
+        resolver: impl Resolver + Clone,
+    ) -> Result<u64, Error> {
+        let resolved = resolve_plog(&self.vlad, &self.head, resolver)
+            .await
+            .map_err(|e| DocumentError::Unresolved(e.to_string()))?;
+        self.verify_with_log(&resolved.log, document)
+    }
+}
+
+/// The [Multikey] stored at the key-path in the state
+fn stored_key(
+    state: &std::collections::BTreeMap<String, LogValue>,
+    key_path: &Key,
+) -> Result<Multikey, Error> {
+    match state.get(key_path.as_str()) {
+        Some(LogValue::Data(data)) => Ok(Multikey::try_from(data.as_slice())
+            .map_err(|_| DocumentError::NoKey(key_path.to_string()))?),
+        _ => Err(DocumentError::NoKey(key_path.to_string()).into()),
+    }
+}
+
+/// The domain separated bytes a document signature is over
+fn signed_bytes(vlad: &Vlad, head: &Cid, key_path: &Key, hash: &Multihash) -> Vec<u8> {
+    let mut v = DOCUMENT_CONTEXT.to_vec();
+    v.append(&mut Varbytes(vlad.clone().into()).encode_into());
+    v.append(&mut Varbytes(head.clone().into()).encode_into());
+    v.append(&mut Varbytes(key_path.as_str().as_bytes().to_vec()).encode_into());
+    v.append(&mut Varbytes(hash.clone().into()).encode_into());
+    v
+}
+
+impl From<DetachedSignature> for Vec<u8> {
+    fn from(signature: DetachedSignature) -> Self {
+        let mut v = Varuint(DETACHED_SIGNATURE_VERSION).encode_into();
+        v.append(&mut Varbytes(signature.vlad.into()).encode_into());
+        v.append(&mut Varbytes(signature.head.into()).encode_into());
+        v.append(&mut Varbytes(signature.key_path.as_str().as_bytes().to_vec()).encode_into());
+        v.append(&mut Varbytes(signature.hash.into()).encode_into());
+        v.append(&mut Varbytes(signature.signature.into()).encode_into());
+        v
+    }
+}
+
+impl TryFrom<&[u8]> for DetachedSignature {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (version, ptr) = Varuint::<u64>::try_decode_from(bytes)?;
+        if version.to_inner() != DETACHED_SIGNATURE_VERSION {
+            return Err(
+                PlogError::InvalidEncoding("unsupported detached signature version").into(),
+            );
+        }
+        let (vlad, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (head, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (key_path, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (hash, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (signature, ptr) = Varbytes::try_decode_from(ptr)?;
+        if !ptr.is_empty() {
+            return Err(PlogError::InvalidEncoding("trailing bytes").into());
+        }
+        let key_path = String::from_utf8(key_path.to_inner())
+            .map_err(|_| PlogError::InvalidEncoding("key-path is not utf-8"))?;
+
+        Ok(Self {
+            vlad: Vlad::try_from(vlad.to_inner().as_slice())?,
+            head: Cid::try_from(head.to_inner().as_slice())?,
+            key_path: Key::try_from(key_path.as_str())?,
+            hash: Multihash::try_from(hash.to_inner().as_slice())?,
+            signature: Multisig::try_from(signature.to_inner().as_slice())?,
+        })
+    }
+}
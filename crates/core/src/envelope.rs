@@ -0,0 +1,185 @@
+//! The interchange format for plogs.
+//!
+//! Every blob starts with the [MAGIC] bytes, then the format version and the
+//! [EnvelopeKind] as unsigned varints, followed by the payload:
+//!
+//! - [EnvelopeKind::Log]: the binary encoding of the whole [Log]
+//! - [EnvelopeKind::Partial]: the [Vlad], then a count of entries and each
+//!   [Entry] as length prefixed bytes, in seqno order
+//! - [EnvelopeKind::Entry]: the binary encoding of a single [Entry]
+//!
+//! [decode_log] also accepts the older encodings, the bare binary [Log] and, with
+//! the `legacy-cbor` feature, the serde_cbor blobs core-bindings used to produce.
+
+use provenance_log::{multicid, multitrait, multiutil};
+
+use multicid::Vlad;
+use multitrait::{EncodeInto, TryDecodeFrom};
+use multiutil::{Varbytes, Varuint};
+use provenance_log::{Entry, Log};
+use std::ops::RangeInclusive;
+
+use crate::{error::EnvelopeError, Error};
+
+/// The bytes every envelope starts with
+pub const MAGIC: &[u8; 4] = b"BSPL";
+
+/// The current version of the envelope format
+pub const ENVELOPE_VERSION: u64 = 1;
+
+/// What an envelope holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvelopeKind {
+    /// A whole [Log]
+    Log = 0,
+    /// A range of entries of a log
+    Partial = 1,
+    /// A single [Entry]
+    Entry = 2,
+}
+
+impl TryFrom<u64> for EnvelopeKind {
+    type Error = EnvelopeError;
+
+    fn try_from(kind: u64) -> Result<Self, Self::Error> {
+        match kind {
+            0 => Ok(Self::Log),
+            1 => Ok(Self::Partial),
+            2 => Ok(Self::Entry),
+            _ => Err(EnvelopeError::UnknownKind(kind)),
+        }
+    }
+}
+
+/// A range of entries from a log, such as the entries new since a known head
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartialLog {
+    /// The plog the entries belong to
+    pub vlad: Vlad,
+    /// The entries, in seqno order
+    pub entries: Vec<Entry>,
+}
+
+impl PartialLog {
+    /// The entries of the [Log] with seqnos in the range
+    pub fn from_log(log: &Log, seqnos: RangeInclusive<u64>) -> Self {
+        let mut entries: Vec<Entry> = log
+            .entries
+            .values()
+            .filter(|entry| seqnos.contains(&entry.seqno()))
+            .cloned()
+            .collect();
+        entries.sort_by_key(|entry| entry.seqno());
+        Self {
+            vlad: log.vlad.clone(),
+            entries,
+        }
+    }
+}
+
+/// The contents of an envelope
+#[derive(Clone, Debug, PartialEq)]
+pub enum Envelope {
+    /// A whole [Log]
+    Log(Log),
+    /// A range of entries of a log
+    Partial(PartialLog),
+    /// A single [Entry]
+    Entry(Entry),
+}
+
+impl Envelope {
+    /// The kind of the contents
+    pub fn kind(&self) -> EnvelopeKind {
+        match self {
+            Self::Log(_) => EnvelopeKind::Log,
+            Self::Partial(_) => EnvelopeKind::Partial,
+            Self::Entry(_) => EnvelopeKind::Entry,
+        }
+    }
+
+    /// Encodes the envelope
+    pub fn encode(&self) -> Vec<u8> {
+        let mut v = MAGIC.to_vec();
+        v.append(&mut Varuint(ENVELOPE_VERSION).encode_into());
+        v.append(&mut Varuint(self.kind() as u64).encode_into());
+        match self {
+            Self::Log(log) => {
+                let log: Vec<u8> = log.clone().into();
+                v.extend_from_slice(&log);
+            }
+            Self::Partial(partial) => {
+                v.append(&mut Varbytes(partial.vlad.clone().into()).encode_into());
+                v.append(&mut Varuint(partial.entries.len()).encode_into());
+                for entry in &partial.entries {
+                    v.append(&mut Varbytes(entry.clone().into()).encode_into());
+                }
+            }
+            Self::Entry(entry) => {
+                let entry: Vec<u8> = entry.clone().into();
+                v.extend_from_slice(&entry);
+            }
+        }
+        v
+    }
+
+    /// Decodes an envelope
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let rest = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or(EnvelopeError::BadMagic)?;
+        let (version, rest) = Varuint::<u64>::try_decode_from(rest)?;
+        let version = version.to_inner();
+        if version != ENVELOPE_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version).into());
+        }
+        let (kind, rest) = Varuint::<u64>::try_decode_from(rest)?;
+        match EnvelopeKind::try_from(kind.to_inner())? {
+            EnvelopeKind::Log => Ok(Self::Log(Log::try_from(rest)?)),
+            EnvelopeKind::Partial => {
+                let (vlad, rest) = Varbytes::try_decode_from(rest)?;
+                let (count, mut rest) = Varuint::<usize>::try_decode_from(rest)?;
+                let mut entries = Vec::new();
+                for _ in 0..count.to_inner() {
+                    let (entry, next) = Varbytes::try_decode_from(rest)?;
+                    entries.push(Entry::try_from(entry.to_inner().as_slice())?);
+                    rest = next;
+                }
+                if !rest.is_empty() {
+                    return Err(EnvelopeError::TrailingBytes.into());
+                }
+                Ok(Self::Partial(PartialLog {
+                    vlad: Vlad::try_from(vlad.to_inner().as_slice())?,
+                    entries,
+                }))
+            }
+            EnvelopeKind::Entry => Ok(Self::Entry(Entry::try_from(rest)?)),
+        }
+    }
+}
+
+/// Encodes the [Log] in an envelope
+pub fn encode_log(log: &Log) -> Vec<u8> {
+    Envelope::Log(log.clone()).encode()
+}
+
+/// Decodes a [Log] from an envelope, or from one of the older encodings
+pub fn decode_log(bytes: &[u8]) -> Result<Log, Error> {
+    if bytes.starts_with(MAGIC) {
+        return match Envelope::decode(bytes)? {
+            Envelope::Log(log) => Ok(log),
+            other => Err(EnvelopeError::WrongKind(other.kind()).into()),
+        };
+    }
+
+    if let Ok(log) = Log::try_from(bytes) {
+        return Ok(log);
+    }
+
+    #[cfg(feature = "legacy-cbor")]
+    if let Ok(log) = serde_cbor::from_slice::<Log>(bytes) {
+        return Ok(log);
+    }
+
+    Err(EnvelopeError::BadMagic.into())
+}
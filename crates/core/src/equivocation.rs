@@ -0,0 +1,231 @@
+//! Equivocation fraud proofs.
+//!
+//! A plog is a single chain of entries. If the holder of a plog key signs two
+//! different entries with the same `prev` and seqno, they have equivocated: they
+//! told different peers different histories. An [EquivocationProof] carries both
+//! signed entries, the [Cid] of their shared ancestor and the keys that signed them.
+//!
+//! The embedded signers prove nothing on their own, anyone can sign two entries
+//! with a throwaway key. A third party checks a proof against keys it trusts for
+//! the plog, with [verify_with_keys](EquivocationProof::verify_with_keys), or
+//! against a verified log holding the ancestor, with
+//! [verify_with_log](EquivocationProof::verify_with_log).
+
+use provenance_log::{multicid, multikey, multitrait, multiutil};
+
+use multicid::{Cid, Vlad};
+use multikey::Multikey;
+use multitrait::{EncodeInto, TryDecodeFrom};
+use multiutil::{Varbytes, Varuint};
+use provenance_log::{Entry, Log, LogValue};
+
+use crate::{
+    error::{EquivocationError, PlogError},
+    history::state_at_cid,
+    resolve::{
+        fork_choice::{relation, ForkChoice, Outcome, Relation},
+        ResolvedPlog,
+    },
+    utils::{multikeys, verify_entry_proof},
+    Error,
+};
+
+/// The version of the binary encoding of [EquivocationProof]
+const EQUIVOCATION_PROOF_VERSION: u64 = 1;
+
+/// Two conflicting signed entries for the same [Vlad], seqno and ancestor
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EquivocationProof {
+    /// The plog the entries belong to
+    pub vlad: Vlad,
+    /// The [Cid] of the entry both entries point to as `prev`, null for conflicting first entries
+    pub ancestor: Cid,
+    /// The first conflicting entry
+    pub first: Entry,
+    /// The public key that signed the first entry
+    pub first_signer: Multikey,
+    /// The second conflicting entry
+    pub second: Entry,
+    /// The public key that signed the second entry
+    pub second_signer: Multikey,
+}
+
+impl EquivocationProof {
+    /// Builds the proof from two verified logs for the same [Vlad] that fork.
+    ///
+    /// The signers are looked up among the keys in each log's state around the
+    /// conflicting entry.
+    pub fn from_resolved(a: &ResolvedPlog, b: &ResolvedPlog) -> Result<Self, Error> {
+        if a.log.vlad != b.log.vlad {
+            return Err(EquivocationError::VladMismatch.into());
+        }
+
+        let Relation::Fork {
+            seqno,
+            ours,
+            theirs,
+            ..
+        } = relation(&a.log, &b.log)
+        else {
+            return Err(EquivocationError::NoFork.into());
+        };
+
+        let first = a.log.entries[&ours].clone();
+        let second = b.log.entries[&theirs].clone();
+
+        let first_signer = find_signer(&a.log, &first).ok_or(EquivocationError::NoSigner(seqno))?;
+        let second_signer =
+            find_signer(&b.log, &second).ok_or(EquivocationError::NoSigner(seqno))?;
+
+        let proof = Self {
+            vlad: a.log.vlad.clone(),
+            ancestor: first.prev(),
+            first,
+            first_signer,
+            second,
+            second_signer,
+        };
+        proof.check_structure()?;
+        Ok(proof)
+    }
+
+    /// Builds a proof for every losing candidate of the [ForkChoice] that forks from the winner.
+    ///
+    /// Candidates for which no proof can be built, such as forks signed by keys that are
+    /// no longer in the state, are skipped.
+    pub fn from_fork_choice(choice: &ForkChoice) -> Vec<Self> {
+        choice
+            .losers
+            .iter()
+            .filter_map(|loser| match &loser.outcome {
+                Outcome::Valid {
+                    relation: Relation::Fork { .. },
+                    resolved,
+                    ..
+                } => Self::from_resolved(&choice.winner, resolved).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The seqno both entries claim
+    pub fn seqno(&self) -> u64 {
+        self.first.seqno()
+    }
+
+    /// Checks that both signers are among the trusted keys, that the entries
+    /// conflict and that each is signed by its signer.
+    pub fn verify_with_keys(&self, trusted: &[Multikey]) -> Result<(), Error> {
+        if !trusted.contains(&self.first_signer) {
+            return Err(EquivocationError::UntrustedSigner(0).into());
+        }
+        if !trusted.contains(&self.second_signer) {
+            return Err(EquivocationError::UntrustedSigner(1).into());
+        }
+        self.check_structure()
+    }
+
+    /// Like [verify_with_keys](Self::verify_with_keys), trusting the keys in the
+    /// state of the verified [Log] right after the ancestor entry.
+    ///
+    /// Conflicting first entries have no ancestor in any log, so they never verify.
+    pub fn verify_with_log(&self, log: &Log) -> Result<(), Error> {
+        if log.vlad != self.vlad {
+            return Err(EquivocationError::VladMismatch.into());
+        }
+        let state = state_at_cid(log, &self.ancestor)?;
+        let trusted: Vec<Multikey> = state
+            .state
+            .values()
+            .filter_map(|value| match value {
+                LogValue::Data(data) => Multikey::try_from(data.as_slice()).ok(),
+                _ => None,
+            })
+            .collect();
+        self.verify_with_keys(&trusted)
+    }
+
+    /// Checks that the entries conflict and that each is signed by its embedded
+    /// signer, which does not tie the signers to the plog.
+    fn check_structure(&self) -> Result<(), Error> {
+        if self.first.vlad() != self.vlad || self.second.vlad() != self.vlad {
+            return Err(EquivocationError::VladMismatch.into());
+        }
+        if self.first.cid() == self.second.cid() {
+            return Err(EquivocationError::SameEntry.into());
+        }
+        if self.first.seqno() != self.second.seqno() {
+            return Err(EquivocationError::SeqnoMismatch.into());
+        }
+        if self.first.prev() != self.ancestor || self.second.prev() != self.ancestor {
+            return Err(EquivocationError::AncestorMismatch.into());
+        }
+        if !verify_entry_proof(&self.first, &self.first_signer) {
+            return Err(EquivocationError::InvalidProof(0).into());
+        }
+        if !verify_entry_proof(&self.second, &self.second_signer) {
+            return Err(EquivocationError::InvalidProof(1).into());
+        }
+        Ok(())
+    }
+}
+
+/// Finds the public key in the [Log] state that signed the [Entry].
+///
+/// The signing key is in the state before the entry, or for a first entry, in
+/// the state the entry itself creates.
+fn find_signer(log: &Log, entry: &Entry) -> Option<Multikey> {
+    let seqno = entry.seqno() as usize;
+    log.verify()
+        .take(seqno + 1)
+        .skip(seqno.saturating_sub(1))
+        .filter_map(|ret| ret.ok())
+        .flat_map(|(_, _, kvp)| multikeys(kvp.iter()))
+        .map(|(_, mk)| mk)
+        .find(|mk| verify_entry_proof(entry, mk))
+}
+
+impl From<EquivocationProof> for Vec<u8> {
+    fn from(proof: EquivocationProof) -> Self {
+        let mut v = Varuint(EQUIVOCATION_PROOF_VERSION).encode_into();
+        v.append(&mut Varbytes(proof.vlad.into()).encode_into());
+        v.append(&mut Varbytes(proof.ancestor.into()).encode_into());
+        v.append(&mut Varbytes(proof.first.into()).encode_into());
+        v.append(&mut Varbytes(proof.first_signer.into()).encode_into());
+        v.append(&mut Varbytes(proof.second.into()).encode_into());
+        v.append(&mut Varbytes(proof.second_signer.into()).encode_into());
+        v
+    }
+}
+
+impl TryFrom<&[u8]> for EquivocationProof {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (version, ptr) = Varuint::<u64>::try_decode_from(bytes)?;
+        if version.to_inner() != EQUIVOCATION_PROOF_VERSION {
+            return Err(
+                PlogError::InvalidEncoding("unsupported equivocation proof version").into(),
+            );
+        }
+        let (vlad, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (ancestor, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (first, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (first_signer, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (second, ptr) = Varbytes::try_decode_from(ptr)?;
+        let (second_signer, ptr) = Varbytes::try_decode_from(ptr)?;
+        if !ptr.is_empty() {
+            return Err(PlogError::InvalidEncoding("trailing bytes").into());
+        }
+
+        Ok(Self {
+            vlad: Vlad::try_from(vlad.to_inner().as_slice())?,
+            ancestor: Cid::try_from(ancestor.to_inner().as_slice())?,
+            first: Entry::try_from(first.to_inner().as_slice())?,
+            first_signer: Multikey::try_from(first_signer.to_inner().as_slice())?,
+            second: Entry::try_from(second.to_inner().as_slice())?,
+            second_signer: Multikey::try_from(second_signer.to_inner().as_slice())?,
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use provenance_log::{multicid, multihash, multikey, multiutil};
+use provenance_log::{multicid, multihash, multikey, multisig, multiutil};
 
 /// Errors generated from this crate
 #[derive(Debug, thiserror::Error)]
@@ -15,6 +15,61 @@ pub enum Error {
     #[error(transparent)]
     Plog(#[from] PlogError),
 
+    /// Equivocation proof errors
+    #[error(transparent)]
+    Equivocation(#[from] EquivocationError),
+
+    /// Inclusion proof errors
+    #[error(transparent)]
+    Inclusion(#[from] InclusionError),
+
+    /// Checkpoint errors
+    #[error(transparent)]
+    Checkpoint(#[from] CheckpointError),
+
+    /// History query errors
+    #[error(transparent)]
+    History(#[from] HistoryError),
+
+    /// Envelope encoding errors
+    #[error(transparent)]
+    Envelope(#[from] EnvelopeError),
+
+    /// JSON import errors
+    #[error(transparent)]
+    Json(#[from] JsonError),
+
+    /// Keystore errors
+    #[error(transparent)]
+    Keystore(#[from] KeystoreError),
+
+    /// Key manager errors
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+
+    /// Detached document signature errors
+    #[error(transparent)]
+    Document(#[from] DocumentError),
+
+    /// DID errors
+    #[error(transparent)]
+    Did(#[from] DidError),
+
+    /// Getting a key from the key manager failed while creating or updating a plog
+    #[error("Key generation for {key} failed: {source}")]
+    KeyGeneration {
+        /// The key-path of the requested key
+        key: String,
+        /// Why the key manager failed
+        source: Box<Error>,
+    },
+    /// Signing the Vlad failed while creating a plog
+    #[error("Vlad signing failed: {0}")]
+    VladSigning(#[source] Box<Error>),
+    /// Signing an entry failed while creating or updating a plog
+    #[error("Entry signing failed: {0}")]
+    EntrySigning(#[source] Box<Error>),
+
     /// Multikey error
     #[error(transparent)]
     Multikey(#[from] multikey::Error),
@@ -24,6 +79,9 @@ pub enum Error {
     /// Multicid error
     #[error(transparent)]
     Multicid(#[from] multicid::Error),
+    /// Multisig error
+    #[error(transparent)]
+    Multisig(#[from] multisig::Error),
     /// Provenance Log error
     #[error(transparent)]
     ProvenanceLog(#[from] provenance_log::Error),
@@ -36,6 +94,30 @@ pub enum Error {
     MultiUtil(#[from] multiutil::Error),
 }
 
+impl Error {
+    /// The [CryptoError] of the key manager, looking through the stage errors
+    pub fn crypto(&self) -> Option<&CryptoError> {
+        match self {
+            Error::Crypto(e) => Some(e),
+            Error::KeyGeneration { source, .. }
+            | Error::VladSigning(source)
+            | Error::EntrySigning(source) => source.crypto(),
+            _ => None,
+        }
+    }
+
+    /// A [CryptoError::UnsupportedCodec] for the codec if building a key failed on
+    /// its codec, otherwise the error as it is
+    pub fn or_unsupported_codec(self, codec: impl std::fmt::Display) -> Self {
+        match self {
+            Error::Multikey(multikey::Error::UnsupportedCodec(_)) => {
+                CryptoError::UnsupportedCodec(codec.to_string()).into()
+            }
+            e => e,
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum OpenError {
@@ -105,12 +187,261 @@ pub enum PlogError {
     /// No string value given
     #[error("No string value given")]
     NoStringValue,
+    /// Truncated or malformed binary encoding
+    #[error("Invalid encoding: {0}")]
+    InvalidEncoding(&'static str),
+}
+
+/// Equivocation proof errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum EquivocationError {
+    /// The logs do not contain two different entries with the same seqno
+    #[error("The logs do not fork")]
+    NoFork,
+    /// The entries belong to different plogs
+    #[error("The entries have different Vlads")]
+    VladMismatch,
+    /// Both entries are the same
+    #[error("The entries are identical")]
+    SameEntry,
+    /// The entries have different seqnos
+    #[error("The entries have different seqnos")]
+    SeqnoMismatch,
+    /// The entries do not share the ancestor
+    #[error("The entries do not share the ancestor")]
+    AncestorMismatch,
+    /// No key in the log state verifies the entry proof
+    #[error("No signing key found for entry at seqno {0}")]
+    NoSigner(u64),
+    /// An entry proof does not verify against its signer
+    #[error("Invalid proof for entry {0}")]
+    InvalidProof(usize),
+    /// A signer is not among the trusted keys
+    #[error("Untrusted signer for entry {0}")]
+    UntrustedSigner(usize),
+}
+
+/// Inclusion proof and key certificate errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum InclusionError {
+    /// The requested seqno is past the head of the log
+    #[error("Seqno {0} is not in the log")]
+    SeqnoOutOfRange(u64),
+    /// An entry on the path is missing from the log
+    #[error("Missing entry after seqno {0}")]
+    MissingEntry(u64),
+    /// The proof contains no entries
+    #[error("The inclusion proof is empty")]
+    EmptyPath,
+    /// The first entry of the path is not the head
+    #[error("The inclusion proof does not start at the head")]
+    HeadMismatch,
+    /// An entry on the path belongs to another plog
+    #[error("The inclusion proof contains an entry with a different Vlad")]
+    VladMismatch,
+    /// An entry is not linked from the entry before it by prev or lipmaa
+    #[error("Entry at seqno {0} does not link to the next entry in the proof")]
+    BrokenLink(u64),
+    /// The key is not set by any entry at or before the seqno
+    #[error("Key {0} is not set")]
+    KeyNotSet(String),
+    /// The certified entry does not set the key to the value
+    #[error("The certified entry does not set key {0} to the value")]
+    KeyMismatch(String),
+}
+
+/// Checkpoint errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CheckpointError {
+    /// The checkpoint is for another plog
+    #[error("The checkpoint is for a different Vlad")]
+    VladMismatch,
+    /// The checkpointed entry is not in the log
+    #[error("The checkpoint entry at seqno {0} is not in the log")]
+    NotInLog(u64),
+    /// The checkpoint is not at the head of the log
+    #[error("The checkpoint at seqno {0} is not at the head of the log")]
+    NotAtHead(u64),
+    /// The snapshot does not match the verified state
+    #[error("The checkpoint state does not match the verified state at seqno {0}")]
+    StateMismatch(u64),
+    /// A key-path in the snapshot is not a valid key-path
+    #[error("The checkpoint has an invalid key-path {0}")]
+    InvalidKey(String),
+}
+
+/// History query errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HistoryError {
+    /// No verified entry has the seqno
+    #[error("No entry with seqno {0}")]
+    NoSuchSeqno(u64),
+    /// The entry is not in the log
+    #[error("No entry with the given Cid")]
+    NoSuchEntry,
+    /// The logs are for different plogs
+    #[error("The logs have different Vlads")]
+    VladMismatch,
+}
+
+/// Envelope encoding errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum EnvelopeError {
+    /// The bytes are not an envelope, nor an older encoding
+    #[error("Not a plog envelope")]
+    BadMagic,
+    /// The envelope version is not supported
+    #[error("Unsupported envelope version {0}")]
+    UnsupportedVersion(u64),
+    /// The envelope kind is not known
+    #[error("Unknown envelope kind {0}")]
+    UnknownKind(u64),
+    /// The envelope holds something else than expected
+    #[error("The envelope holds a {0:?}")]
+    WrongKind(crate::envelope::EnvelopeKind),
+    /// There are bytes after the payload
+    #[error("Trailing bytes after the envelope payload")]
+    TrailingBytes,
+}
+
+/// JSON import errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum JsonError {
+    /// The string is not valid JSON
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(String),
+    /// The JSON value could not be written out
+    #[error("Serializing JSON failed: {0}")]
+    Serialize(String),
+    /// A required field is missing
+    #[error("Missing field {0}")]
+    MissingField(&'static str),
+    /// A field has the wrong type or an undecodable value
+    #[error("Invalid field {0}")]
+    InvalidField(&'static str),
+    /// The rebuilt entry does not have the stored CID
+    #[error("The rebuilt entry {0} does not match its CID")]
+    CidMismatch(u64),
+    /// An entry belongs to a different plog
+    #[error("Entry {0} has a different Vlad")]
+    VladMismatch(u64),
+}
+
+/// Keystore errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum KeystoreError {
+    /// The password does not unlock the keystore
+    #[error("Wrong keystore password")]
+    WrongPassword,
+    /// A new keystore would overwrite an existing file
+    #[error("The keystore file {0} already exists")]
+    Exists(String),
+    /// The keystore file could not be read or written
+    #[error("Keystore file error: {0}")]
+    Io(String),
+    /// The keystore file is damaged or not a keystore
+    #[error("Invalid keystore file: {0}")]
+    InvalidFile(&'static str),
+    /// Key derivation or encryption failed
+    #[error("Keystore crypto error: {0}")]
+    Crypto(String),
+}
+
+/// Detached document signature errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum DocumentError {
+    /// The signature is for another plog
+    #[error("The signature is for a different Vlad")]
+    VladMismatch,
+    /// The document does not have the signed hash
+    #[error("The document does not match the signed hash")]
+    HashMismatch,
+    /// The signing key is not the key stored at the key-path
+    #[error("The signing key is not the key at {0}")]
+    KeyMismatch(String),
+    /// There is no key at the key-path at the signing entry
+    #[error("No key at {0}")]
+    NoKey(String),
+    /// The signature does not verify
+    #[error("Invalid document signature")]
+    InvalidSignature,
+    /// The plog of the signer could not be resolved
+    #[error("Failed to resolve the signing plog: {0}")]
+    Unresolved(String),
+}
+
+/// DID errors
+#[derive(Clone, Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum DidError {
+    /// The string is not a `did:plog` DID
+    #[error("Invalid plog DID: {0}")]
+    InvalidDid(String),
+    /// The plog of the DID could not be resolved
+    #[error("Failed to resolve the DID: {0}")]
+    Unresolved(String),
+}
+
+/// Key manager errors, returned by [CryptoManager](crate::ops::CryptoManager) impls
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum CryptoError {
+    /// There is no key for the key-path or public key
+    #[error("Key not found: {0}")]
+    KeyNotFound(String),
+    /// The user declined to sign
+    #[error("Signing rejected by the user: {0}")]
+    UserRejected(String),
+    /// The keys are locked away
+    #[error("The keystore is locked")]
+    KeystoreLocked,
+    /// The key manager cannot make keys of the codec
+    #[error("Unsupported key codec {0}")]
+    UnsupportedCodec(String),
+    /// The key manager or its signer could not be reached
+    #[error("Signer unavailable: {0}")]
+    SignerUnavailable(String),
+}
+
+impl CryptoError {
+    /// The stable code of the error, for key managers across a language boundary
+    pub fn code(&self) -> &'static str {
+        match self {
+            CryptoError::KeyNotFound(_) => "key-not-found",
+            CryptoError::UserRejected(_) => "user-rejected",
+            CryptoError::KeystoreLocked => "keystore-locked",
+            CryptoError::UnsupportedCodec(_) => "unsupported-codec",
+            CryptoError::SignerUnavailable(_) => "signer-unavailable",
+        }
+    }
+
+    /// The error with the [code](Self::code), None for unknown codes
+    pub fn from_code(code: &str, reason: impl Into<String>) -> Option<Self> {
+        let reason = reason.into();
+        Some(match code {
+            "key-not-found" => CryptoError::KeyNotFound(reason),
+            "user-rejected" => CryptoError::UserRejected(reason),
+            "keystore-locked" => CryptoError::KeystoreLocked,
+            "unsupported-codec" => CryptoError::UnsupportedCodec(reason),
+            "signer-unavailable" => CryptoError::SignerUnavailable(reason),
+            _ => return None,
+        })
+    }
 }
 
 impl From<Error> for multicid::Error {
     fn from(e: Error) -> Self {
         match e {
             Error::Multicid(e) => e,
+            Error::Multikey(e) => multicid::Error::Multikey(e),
             _ => multicid::Error::Multikey(multikey::Error::Sign(
                 multikey::error::SignError::SigningFailed(e.to_string()),
             )),
@@ -0,0 +1,230 @@
+//! Time travel queries over a verified plog.
+//!
+//! The verifier yields the key-value state after every entry, so the state at any
+//! seqno or entry [Cid] can be read back as a [Checkpoint], and the writes to a
+//! key-path can be collected into its history. [KeyValidity] turns the writes of
+//! keys into the seqnos each key was valid at, to check old signatures after a
+//! key rotation.
+
+use provenance_log::{multicid, multikey};
+
+use multicid::Cid;
+use multikey::Multikey;
+use provenance_log::{Key, Log, LogValue, Op};
+use std::collections::BTreeMap;
+
+use crate::{checkpoint::Checkpoint, error::HistoryError, Error};
+
+/// The kind of change an entry made to a key-path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChangeKind {
+    /// The key-path was set to a value
+    Update,
+    /// The key-path was deleted
+    Delete,
+}
+
+/// One change to a key-path
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyChange {
+    /// The seqno of the entry that made the change
+    pub seqno: u64,
+    /// The [Cid] of the entry that made the change
+    pub cid: Cid,
+    /// The key-path of the op, the queried one, one under it, or a branch above it
+    pub key_path: String,
+    /// What the entry did
+    pub kind: ChangeKind,
+    /// The new value, none for a delete
+    pub value: Option<LogValue>,
+}
+
+/// Verifies the [Log] and returns the state after every entry, from foot to head
+pub fn states(log: &Log) -> Result<Vec<Checkpoint>, Error> {
+    log.verify()
+        .map(|ret| {
+            let (_, entry, kvp) = ret?;
+            Ok(Checkpoint::new(&log.vlad, &entry, kvp.iter()))
+        })
+        .collect()
+}
+
+/// The state of the [Log] right after the entry with the seqno
+pub fn state_at_seqno(log: &Log, seqno: u64) -> Result<Checkpoint, Error> {
+    for ret in log.verify() {
+        let (_, entry, kvp) = ret?;
+        if entry.seqno() == seqno {
+            return Ok(Checkpoint::new(&log.vlad, &entry, kvp.iter()));
+        }
+    }
+    Err(HistoryError::NoSuchSeqno(seqno).into())
+}
+
+/// The state of the [Log] right after the entry with the [Cid]
+pub fn state_at_cid(log: &Log, cid: &Cid) -> Result<Checkpoint, Error> {
+    let seqno = log
+        .entries
+        .get(cid)
+        .ok_or(HistoryError::NoSuchEntry)?
+        .seqno();
+    state_at_seqno(log, seqno)
+}
+
+/// Every change to the key-path in the verified [Log], oldest first
+///
+/// For a branch, such as `/hello/`, this includes the changes to every key-path
+/// under it. Deleting a branch deletes everything under it, so the deletes of the
+/// branches above the key-path are included too.
+pub fn key_history(log: &Log, key: &str) -> Result<Vec<KeyChange>, Error> {
+    let key = Key::try_from(key)?;
+    let mut changes = Vec::new();
+    for ret in log.verify() {
+        let (_, entry, _) = ret?;
+        for op in entry.ops() {
+            let (op_key, kind, value) = match op {
+                Op::Update(k, value) if is_under(k, &key) => {
+                    (k, ChangeKind::Update, Some(value.clone()))
+                }
+                Op::Delete(k) if is_under(k, &key) || is_under(&key, k) => {
+                    (k, ChangeKind::Delete, None)
+                }
+                _ => continue,
+            };
+            changes.push(KeyChange {
+                seqno: entry.seqno(),
+                cid: entry.cid(),
+                key_path: op_key.to_string(),
+                kind,
+                value,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Whether the key-path is the branch or under it, or is the same leaf
+pub(crate) fn is_under(key: &Key, branch: &Key) -> bool {
+    key == branch || (is_branch(branch) && key.as_str().starts_with(branch.as_str()))
+}
+
+/// Whether the key-path is a branch, ending with a slash
+fn is_branch(key: &Key) -> bool {
+    key.as_str().ends_with('/')
+}
+
+/// The seqnos a [Multikey] was stored under a key-path
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidityInterval {
+    /// The stored key
+    pub key: Multikey,
+    /// The seqno of the entry that set the key
+    pub from: u64,
+    /// The seqno of the entry that deleted or replaced the key, none if it is still set
+    pub until: Option<u64>,
+}
+
+impl ValidityInterval {
+    /// Whether the key was set in the state right after the entry with the seqno
+    pub fn contains(&self, seqno: u64) -> bool {
+        seqno >= self.from && self.until.map_or(true, |until| seqno < until)
+    }
+}
+
+/// The validity intervals of every [Multikey] stored in a verified [Log], by key-path
+///
+/// A key is valid from the entry that sets it up to, but not including, the entry
+/// that deletes or replaces it. Rotating with `KeyGen { revoke: true }` deletes and
+/// sets the key-path in the same entry, so the old key ends where the new one starts.
+/// Deleting a branch ends the keys of every key-path under it. Keys set and replaced
+/// within one entry were never in a verified state and have no interval.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeyValidity {
+    intervals: BTreeMap<String, Vec<ValidityInterval>>,
+}
+
+impl KeyValidity {
+    /// Verifies the [Log] and collects the validity intervals of its keys
+    pub fn new(log: &Log) -> Result<Self, Error> {
+        let mut intervals: BTreeMap<String, Vec<ValidityInterval>> = BTreeMap::new();
+        for ret in log.verify() {
+            let (_, entry, _) = ret?;
+            let seqno = entry.seqno();
+            for op in entry.ops() {
+                let (key, value) = match op {
+                    Op::Update(key, value) => (key, Some(value)),
+                    Op::Delete(key) => (key, None),
+                    Op::Noop(_) => continue,
+                };
+
+                // any write ends the current key, and deleting a branch ends the
+                // keys under it
+                if value.is_none() && is_branch(key) {
+                    for (key_path, key_intervals) in intervals.iter_mut() {
+                        if key_path.starts_with(key.as_str()) {
+                            end_current(key_intervals, seqno);
+                        }
+                    }
+                }
+                let key_intervals = intervals.entry(key.to_string()).or_default();
+                end_current(key_intervals, seqno);
+
+                if let Some(LogValue::Data(data)) = value {
+                    if let Ok(mk) = Multikey::try_from(data.as_slice()) {
+                        key_intervals.push(ValidityInterval {
+                            key: mk,
+                            from: seqno,
+                            until: None,
+                        });
+                    }
+                }
+            }
+        }
+        intervals.retain(|_, key_intervals| !key_intervals.is_empty());
+        Ok(Self { intervals })
+    }
+
+    /// The validity intervals of the keys stored under the key-path, oldest first
+    pub fn intervals(&self, key_path: &str) -> &[ValidityInterval] {
+        self.intervals
+            .get(key_path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The key-paths that ever held a key
+    pub fn key_paths(&self) -> impl Iterator<Item = &str> {
+        self.intervals.keys().map(String::as_str)
+    }
+
+    /// The key stored under the key-path right after the entry with the seqno
+    pub fn key_at(&self, key_path: &str, seqno: u64) -> Option<&Multikey> {
+        self.intervals(key_path)
+            .iter()
+            .find(|interval| interval.contains(seqno))
+            .map(|interval| &interval.key)
+    }
+
+    /// Whether the public key was stored under the key-path right after the entry with
+    /// the seqno
+    ///
+    /// An entry is signed with a key valid after the entry before it, so to check
+    /// the signer of the entry at seqno `n > 0`, ask for `n - 1`.
+    pub fn was_valid(&self, key_path: &str, pubkey: &Multikey, seqno: u64) -> bool {
+        self.key_at(key_path, seqno) == Some(pubkey)
+    }
+}
+
+/// Ends the current interval at the seqno, dropping it if it started there too
+fn end_current(key_intervals: &mut Vec<ValidityInterval>, seqno: u64) {
+    if let Some(current) = key_intervals.last_mut() {
+        if current.until.is_none() {
+            current.until = Some(seqno);
+            if current.from == seqno {
+                key_intervals.pop();
+            }
+        }
+    }
+}
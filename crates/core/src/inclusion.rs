@@ -0,0 +1,214 @@
+//! Succinct inclusion proofs and key certificates.
+//!
+//! Every entry links to the entry before it with `prev`, and entries at lipmaa
+//! seqnos also carry a long-hop `lipmaa` link further back. Following the long hops
+//! whenever they do not overshoot reaches any earlier entry from the head in
+//! O(log n) entries. An [InclusionProof] is that path, and since every link is a
+//! content address, anyone who trusts the head [Cid] can check that the last entry
+//! on the path is part of the log.
+//!
+//! A [KeyCertificate] adds a key-path and value to an inclusion proof and shows that
+//! the certified entry wrote that value, so a light client can check a key binding
+//! such as `/pubkey` without downloading the whole plog.
+
+use provenance_log::{multicid, multitrait, multiutil};
+
+use multicid::{Cid, Vlad};
+use multitrait::{EncodeInto, TryDecodeFrom};
+use multiutil::{Varbytes, Varuint};
+use provenance_log::{Entry, Key, Log, LogValue, Op};
+
+use crate::{
+    error::{InclusionError, PlogError},
+    history::is_under,
+    Error,
+};
+
+/// The version of the binary encoding of [InclusionProof]
+const INCLUSION_PROOF_VERSION: u64 = 1;
+
+/// A path of entries from the head of a log down to an earlier entry
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InclusionProof {
+    /// The entries on the path, starting with the head and ending with the included entry
+    pub path: Vec<Entry>,
+}
+
+impl InclusionProof {
+    /// Builds the shortest lipmaa path from the head of the [Log] to the entry at `seqno`
+    pub fn new(log: &Log, seqno: u64) -> Result<Self, Error> {
+        let mut current = log
+            .entries
+            .get(&log.head)
+            .ok_or(InclusionError::SeqnoOutOfRange(seqno))?;
+        if seqno > current.seqno() {
+            return Err(InclusionError::SeqnoOutOfRange(seqno).into());
+        }
+
+        let mut path = vec![current.clone()];
+        while current.seqno() > seqno {
+            // take the long hop unless it jumps past the target
+            current = match log.entries.get(&current.lipmaa()) {
+                Some(longhop) if longhop.seqno() >= seqno => longhop,
+                _ => log
+                    .entries
+                    .get(&current.prev())
+                    .ok_or(InclusionError::MissingEntry(current.seqno()))?,
+            };
+            path.push(current.clone());
+        }
+
+        Ok(Self { path })
+    }
+
+    /// The included entry, the last one on the path
+    pub fn entry(&self) -> Option<&Entry> {
+        self.path.last()
+    }
+
+    /// The seqno of the included entry
+    pub fn seqno(&self) -> Option<u64> {
+        self.entry().map(|entry| entry.seqno())
+    }
+
+    /// Checks the path against a trusted [Vlad] and head [Cid] and returns the included entry.
+    ///
+    /// The head is usually taken from a naming record or a fully verified log. The
+    /// proof only shows that the entry is committed to by the head, the head itself
+    /// is not verified.
+    pub fn verify(&self, vlad: &Vlad, head: &Cid) -> Result<&Entry, Error> {
+        let first = self.path.first().ok_or(InclusionError::EmptyPath)?;
+        if first.cid() != *head {
+            return Err(InclusionError::HeadMismatch.into());
+        }
+        if self.path.iter().any(|entry| entry.vlad() != *vlad) {
+            return Err(InclusionError::VladMismatch.into());
+        }
+        for pair in self.path.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let cid = to.cid();
+            if to.seqno() >= from.seqno() || (cid != from.prev() && cid != from.lipmaa()) {
+                return Err(InclusionError::BrokenLink(from.seqno()).into());
+            }
+        }
+        self.entry().ok_or_else(|| InclusionError::EmptyPath.into())
+    }
+}
+
+impl From<InclusionProof> for Vec<u8> {
+    fn from(proof: InclusionProof) -> Self {
+        let mut v = Varuint(INCLUSION_PROOF_VERSION).encode_into();
+        v.append(&mut Varuint(proof.path.len()).encode_into());
+        for entry in proof.path {
+            v.append(&mut Varbytes(entry.into()).encode_into());
+        }
+        v
+    }
+}
+
+impl TryFrom<&[u8]> for InclusionProof {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (version, ptr) = Varuint::<u64>::try_decode_from(bytes)?;
+        if version.to_inner() != INCLUSION_PROOF_VERSION {
+            return Err(PlogError::InvalidEncoding("unsupported inclusion proof version").into());
+        }
+        let (len, mut ptr) = Varuint::<usize>::try_decode_from(ptr)?;
+        let mut path = Vec::new();
+        for _ in 0..len.to_inner() {
+            let (entry, next) = Varbytes::try_decode_from(ptr)?;
+            path.push(Entry::try_from(entry.to_inner().as_slice())?);
+            ptr = next;
+        }
+        if !ptr.is_empty() {
+            return Err(PlogError::InvalidEncoding("trailing bytes").into());
+        }
+        Ok(Self { path })
+    }
+}
+
+/// A certificate that an entry in a log set a key-path to a value.
+///
+/// The certificate proves that the value was written by the certified entry and
+/// that the entry is part of the log with the trusted head. It does not prove the
+/// absence of later writes: an entry after the certified one may have changed or
+/// deleted the key, and ruling that out needs every entry in between.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyCertificate {
+    /// The inclusion proof of the entry that wrote the value
+    pub proof: InclusionProof,
+    /// The key-path
+    pub key: Key,
+    /// The value the entry wrote
+    pub value: LogValue,
+}
+
+impl KeyCertificate {
+    /// Certifies the value of the key-path as of `seqno`, from the latest entry at or
+    /// before `seqno` that writes the key.
+    pub fn new(log: &Log, key: &Key, seqno: u64) -> Result<Self, Error> {
+        let head = log
+            .entries
+            .get(&log.head)
+            .ok_or(InclusionError::SeqnoOutOfRange(seqno))?;
+        if seqno > head.seqno() {
+            return Err(InclusionError::SeqnoOutOfRange(seqno).into());
+        }
+
+        let writer = log
+            .entries
+            .values()
+            .filter(|entry| entry.seqno() <= seqno)
+            .filter_map(|entry| last_write(entry, key).map(|op| (entry.seqno(), op)))
+            .max_by_key(|(seqno, _)| *seqno);
+
+        match writer {
+            Some((seqno, Op::Update(_, value))) => Ok(Self {
+                proof: InclusionProof::new(log, seqno)?,
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            _ => Err(InclusionError::KeyNotSet(key.to_string()).into()),
+        }
+    }
+
+    /// Certifies the value of the key-path at the head of the [Log]
+    pub fn latest(log: &Log, key: &Key) -> Result<Self, Error> {
+        let head = log
+            .entries
+            .get(&log.head)
+            .ok_or(InclusionError::SeqnoOutOfRange(0))?;
+        Self::new(log, key, head.seqno())
+    }
+
+    /// The seqno of the entry that wrote the value
+    pub fn seqno(&self) -> Option<u64> {
+        self.proof.seqno()
+    }
+
+    /// Checks the certificate against a trusted [Vlad] and head [Cid] and returns the
+    /// certified value.
+    pub fn verify(&self, vlad: &Vlad, head: &Cid) -> Result<&LogValue, Error> {
+        let entry = self.proof.verify(vlad, head)?;
+        match last_write(entry, &self.key) {
+            Some(Op::Update(_, value)) if *value == self.value => Ok(&self.value),
+            _ => Err(InclusionError::KeyMismatch(self.key.to_string()).into()),
+        }
+    }
+}
+
+/// The last op in the [Entry] that writes the key-path, if any, counting the delete
+/// of a branch it is under
+fn last_write<'a>(entry: &'a Entry, key: &Key) -> Option<&'a Op> {
+    entry
+        .ops()
+        .filter(|op| match op {
+            Op::Update(k, _) => k == key,
+            Op::Delete(k) => is_under(key, k),
+            Op::Noop(_) => false,
+        })
+        .last()
+}
@@ -0,0 +1,252 @@
+//! A lossless JSON representation of plogs, for interop and debugging.
+//!
+//! The layout follows the DAG-JSON conventions so other IPLD tooling can read it:
+//!
+//! - CIDs are links, `{"/": "<base32 cid>"}`, and a null CID is `null`
+//! - the [Vlad] and all other bytes are multibase strings
+//! - scripts are objects with their `path` and one of `code` (the script text),
+//!   `bin` (multibase bytes) or `cid` (a link)
+//! - ops are objects with `op` ("noop", "delete" or "update"), `path` and, for
+//!   updates, one of a `str`, a multibase `data` or a null `nil` value
+//!
+//! Every entry keeps its `proof`, so [log_from_json] rebuilds the exact same entries
+//! and the imported [Log] verifies like the exported one did. The `cid` of every entry
+//! is written out as well and checked on import.
+
+use provenance_log::{multibase, multicid, multitrait};
+
+use multibase::Base;
+use multicid::{Cid, EncodedCid, EncodedVlad, Vlad};
+use multitrait::Null;
+use provenance_log::{entry, vm, Entry, Key, Log, LogValue, Op, Pairs as _, Script};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use crate::{error::JsonError, Error};
+
+/// The base used for CIDs in links
+const CID_BASE: Base = Base::Base32Lower;
+
+/// The base used for the [Vlad] and all bytes
+const BYTES_BASE: Base = Base::Base64;
+
+/// Exports the [Log] as a JSON value
+pub fn log_to_json(log: &Log) -> Value {
+    let mut entries: Vec<&Entry> = log.entries.values().collect();
+    entries.sort_by_key(|entry| entry.seqno());
+    json!({
+        "vlad": EncodedVlad::new(BYTES_BASE, log.vlad.clone()).to_string(),
+        "first_lock": script_to_json(&log.first_lock),
+        "head": link(&log.head),
+        "foot": link(&log.foot),
+        "entries": entries.into_iter().map(entry_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Exports the [Log] as a pretty printed JSON string
+pub fn log_to_json_string(log: &Log) -> Result<String, Error> {
+    Ok(serde_json::to_string_pretty(&log_to_json(log))
+        .map_err(|e| JsonError::Serialize(e.to_string()))?)
+}
+
+/// Exports the [Entry] as a JSON value
+pub fn entry_to_json(entry: &Entry) -> Value {
+    json!({
+        "cid": link(&entry.cid()),
+        "vlad": EncodedVlad::new(BYTES_BASE, entry.vlad()).to_string(),
+        "seqno": entry.seqno(),
+        "prev": link(&entry.prev()),
+        "lipmaa": link(&entry.lipmaa()),
+        "ops": entry.ops().map(op_to_json).collect::<Vec<_>>(),
+        "locks": entry.locks().map(script_to_json).collect::<Vec<_>>(),
+        "unlock": script_to_json(entry.unlock()),
+        "proof": entry_proof_bytes(entry).map(|proof| multibase::encode(BYTES_BASE, proof)),
+    })
+}
+
+/// Imports a [Log] from a JSON value made by [log_to_json]
+pub fn log_from_json(value: &Value) -> Result<Log, Error> {
+    let vlad = vlad_from_json(field(value, "vlad")?)?;
+    let first_lock = script_from_json(field(value, "first_lock")?)?;
+    let head = cid_from_json(field(value, "head")?)?;
+    let foot = cid_from_json(field(value, "foot")?)?;
+
+    let mut entries = BTreeMap::new();
+    for entry in array(field(value, "entries")?, "entries")? {
+        let entry = entry_from_json(entry)?;
+        if entry.vlad() != vlad {
+            return Err(JsonError::VladMismatch(entry.seqno()).into());
+        }
+        entries.insert(entry.cid(), entry);
+    }
+
+    Ok(provenance_log::log::Builder::new()
+        .with_vlad(&vlad)
+        .with_first_lock(&first_lock)
+        .with_entries(&entries)
+        .with_head(&head)
+        .with_foot(&foot)
+        .try_build()?)
+}
+
+/// Imports a [Log] from a JSON string made by [log_to_json_string]
+pub fn log_from_json_str(s: &str) -> Result<Log, Error> {
+    let value: Value =
+        serde_json::from_str(s).map_err(|e| JsonError::InvalidJson(e.to_string()))?;
+    log_from_json(&value)
+}
+
+/// Imports an [Entry] from a JSON value made by [entry_to_json]
+///
+/// The entry is rebuilt with its stored proof, and its CID must match the stored one.
+pub fn entry_from_json(value: &Value) -> Result<Entry, Error> {
+    let cid = cid_from_json(field(value, "cid")?)?;
+    let vlad = vlad_from_json(field(value, "vlad")?)?;
+    let seqno = field(value, "seqno")?
+        .as_u64()
+        .ok_or(JsonError::InvalidField("seqno"))?;
+    let prev = cid_from_json(field(value, "prev")?)?;
+    let lipmaa = cid_from_json(field(value, "lipmaa")?)?;
+    let unlock = script_from_json(field(value, "unlock")?)?;
+    let proof = match field(value, "proof")? {
+        Value::Null => Vec::new(),
+        proof => bytes_from_json(proof, "proof")?,
+    };
+
+    let mut builder = entry::Builder::default()
+        .with_vlad(&vlad)
+        .with_seqno(seqno)
+        .with_unlock(&unlock);
+    if !prev.is_null() {
+        builder = builder.with_prev(&prev);
+    }
+    if !lipmaa.is_null() {
+        builder = builder.with_lipmaa(&lipmaa);
+    }
+    for op in array(field(value, "ops")?, "ops")? {
+        builder = builder.add_op(&op_from_json(op)?);
+    }
+    for lock in array(field(value, "locks")?, "locks")? {
+        builder = builder.add_lock(&script_from_json(lock)?);
+    }
+
+    // the entry is already signed, put the stored proof back in place
+    let entry = builder.try_build(|_| Ok(proof.clone()))?;
+
+    if entry.cid() != cid {
+        return Err(JsonError::CidMismatch(seqno).into());
+    }
+    Ok(entry)
+}
+
+/// The raw proof bytes of the [Entry], if it has one
+fn entry_proof_bytes(entry: &Entry) -> Option<Vec<u8>> {
+    match entry.get("/entry/proof") {
+        Some(vm::Value::Bin { data, .. }) => Some(data),
+        _ => None,
+    }
+}
+
+/// A DAG-JSON link to the [Cid], or null for the null CID
+fn link(cid: &Cid) -> Value {
+    if cid.is_null() {
+        return Value::Null;
+    }
+    json!({ "/": EncodedCid::new(CID_BASE, cid.clone()).to_string() })
+}
+
+fn op_to_json(op: &Op) -> Value {
+    match op {
+        Op::Noop(key) => json!({ "op": "noop", "path": key.to_string() }),
+        Op::Delete(key) => json!({ "op": "delete", "path": key.to_string() }),
+        Op::Update(key, LogValue::Str(s)) => {
+            json!({ "op": "update", "path": key.to_string(), "str": s })
+        }
+        Op::Update(key, LogValue::Data(data)) => json!({
+            "op": "update",
+            "path": key.to_string(),
+            "data": multibase::encode(BYTES_BASE, data),
+        }),
+        Op::Update(key, LogValue::Nil) => {
+            json!({ "op": "update", "path": key.to_string(), "nil": null })
+        }
+    }
+}
+
+fn script_to_json(script: &Script) -> Value {
+    let path = script.path().to_string();
+    match script {
+        Script::Code(_, code) => json!({ "path": path, "code": code }),
+        Script::Bin(_, bin) => json!({ "path": path, "bin": multibase::encode(BYTES_BASE, bin) }),
+        Script::Cid(_, cid) => json!({ "path": path, "cid": link(cid) }),
+    }
+}
+
+fn field<'a>(value: &'a Value, name: &'static str) -> Result<&'a Value, JsonError> {
+    value.get(name).ok_or(JsonError::MissingField(name))
+}
+
+fn array<'a>(value: &'a Value, name: &'static str) -> Result<&'a Vec<Value>, JsonError> {
+    value.as_array().ok_or(JsonError::InvalidField(name))
+}
+
+fn string<'a>(value: &'a Value, name: &'static str) -> Result<&'a str, JsonError> {
+    value.as_str().ok_or(JsonError::InvalidField(name))
+}
+
+fn key_from_json(value: &Value) -> Result<Key, Error> {
+    Ok(Key::try_from(string(value, "path")?)?)
+}
+
+fn bytes_from_json(value: &Value, name: &'static str) -> Result<Vec<u8>, JsonError> {
+    let (_, bytes) =
+        multibase::decode(string(value, name)?).map_err(|_| JsonError::InvalidField(name))?;
+    Ok(bytes)
+}
+
+fn vlad_from_json(value: &Value) -> Result<Vlad, JsonError> {
+    EncodedVlad::try_from(string(value, "vlad")?)
+        .map(|encoded| encoded.to_inner())
+        .map_err(|_| JsonError::InvalidField("vlad"))
+}
+
+fn cid_from_json(value: &Value) -> Result<Cid, JsonError> {
+    if value.is_null() {
+        return Ok(Cid::null());
+    }
+    EncodedCid::try_from(string(field(value, "/")?, "/")?)
+        .map(|encoded| encoded.to_inner())
+        .map_err(|_| JsonError::InvalidField("/"))
+}
+
+fn op_from_json(value: &Value) -> Result<Op, Error> {
+    let key = key_from_json(field(value, "path")?)?;
+    match string(field(value, "op")?, "op")? {
+        "noop" => Ok(Op::Noop(key)),
+        "delete" => Ok(Op::Delete(key)),
+        "update" => {
+            let value = match (value.get("str"), value.get("data"), value.get("nil")) {
+                (Some(s), None, None) => LogValue::Str(string(s, "str")?.to_string()),
+                (None, Some(data), None) => LogValue::Data(bytes_from_json(data, "data")?),
+                (None, None, Some(Value::Null)) => LogValue::Nil,
+                _ => return Err(JsonError::InvalidField("update").into()),
+            };
+            Ok(Op::Update(key, value))
+        }
+        _ => Err(JsonError::InvalidField("op").into()),
+    }
+}
+
+fn script_from_json(value: &Value) -> Result<Script, Error> {
+    let path = key_from_json(field(value, "path")?)?;
+    let object: &Map<String, Value> = value.as_object().ok_or(JsonError::InvalidField("script"))?;
+    if let Some(code) = object.get("code") {
+        Ok(Script::Code(path, string(code, "code")?.to_string()))
+    } else if let Some(bin) = object.get("bin") {
+        Ok(Script::Bin(path, bytes_from_json(bin, "bin")?))
+    } else if let Some(cid) = object.get("cid") {
+        Ok(Script::Cid(path, cid_from_json(cid)?))
+    } else {
+        Err(JsonError::InvalidField("script").into())
+    }
+}
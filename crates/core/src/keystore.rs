@@ -0,0 +1,462 @@
+//! An encrypted on-disk keystore.
+//!
+//! The keystore keeps the secret [Multikey]s of any number of plogs in one file,
+//! each under a plog label and the key-path it is used for, such as `/pubkey`.
+//!
+//! The file is encrypted with a key derived from a password with Argon2id. Every
+//! secret key is sealed on its own with XChaCha20-Poly1305, using a random nonce
+//! and the label and key-path as associated data, so sealed keys cannot be moved
+//! to another slot. A sealed check value lets [Keystore::unlock] tell a wrong
+//! password apart from a damaged file. Labels and key-paths are not secret and can
+//! be listed while the keystore is locked.
+//!
+//! The file layout is the [MAGIC] bytes, the format version, the Argon2id memory
+//! cost, time cost and parallelism, the salt and the sealed check value, then a
+//! count of slots and, for each slot, the label, the key-path, the nonce and the
+//! sealed key. Numbers are unsigned varints and byte strings are length prefixed.
+//! Version 1 files have no Argon2id parameters and are read with the ones new
+//! keystores use.
+//!
+//! [Keystore] implements [CryptoManager]: keys generated while creating or
+//! updating a plog are stored under the label set with [Keystore::select], and
+//! written out with the next [Keystore::save]. The one-time Vlad and entry keys
+//! are never stored, they only sign the first entry.
+
+use provenance_log::{multicodec, multikey, multisig, multitrait, multiutil};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use multicodec::Codec;
+use multikey::{mk, EncodedMultikey, Multikey, Views as _};
+use multisig::Multisig;
+use multitrait::{EncodeInto, TryDecodeFrom};
+use multiutil::{Varbytes, Varuint};
+use provenance_log::Key;
+use rand::{rngs::OsRng, RngCore};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use crate::{
+    error::{CryptoError, KeystoreError},
+    ops::{
+        config::defaults::{DEFAULT_ENTRYKEY, DEFAULT_VLAD_KEY},
+        CryptoManager,
+    },
+    Base, Error,
+};
+
+/// The bytes every keystore file starts with
+pub const MAGIC: &[u8; 4] = b"BSKS";
+
+/// The current version of the keystore format
+pub const KEYSTORE_VERSION: u64 = 2;
+
+/// The Argon2id memory cost of new keystores, in KiB
+const KDF_M_COST: u32 = 19 * 1024;
+/// The Argon2id time cost of new keystores
+const KDF_T_COST: u32 = 2;
+/// The Argon2id parallelism of new keystores
+const KDF_P_COST: u32 = 1;
+
+/// The plaintext of the sealed check value
+const CHECK: &[u8] = b"bestsign keystore";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// A sealed secret key
+#[derive(Clone, Debug)]
+struct Sealed {
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+/// An encrypted file of secret keys, by plog label and key-path
+pub struct Keystore {
+    path: PathBuf,
+    /// The Argon2id parameters the key is derived with
+    params: Params,
+    salt: [u8; SALT_LEN],
+    check: Sealed,
+    slots: BTreeMap<(String, String), Sealed>,
+    /// The password derived key, only while unlocked
+    key: Option<Zeroizing<[u8; 32]>>,
+    /// The plog label used by the [CryptoManager] impl
+    label: String,
+}
+
+impl Keystore {
+    /// Creates a new, empty and unlocked keystore file
+    ///
+    /// Fails if the file already exists.
+    pub fn create(path: impl AsRef<Path>, password: &[u8]) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            return Err(KeystoreError::Exists(path.display().to_string()).into());
+        }
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let params = kdf_params(KDF_M_COST, KDF_T_COST, KDF_P_COST)?;
+        let key = derive_key(password, &salt, &params)?;
+        let check = seal(&key, CHECK, CHECK)?;
+        let keystore = Self {
+            path,
+            params,
+            salt,
+            check,
+            slots: BTreeMap::new(),
+            key: Some(key),
+            label: String::new(),
+        };
+        keystore.save()?;
+        Ok(keystore)
+    }
+
+    /// Opens an existing keystore file, locked
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let bytes = std::fs::read(&path).map_err(|e| KeystoreError::Io(e.to_string()))?;
+        let (params, salt, check, slots) = decode(&bytes)?;
+        Ok(Self {
+            path,
+            params,
+            salt,
+            check,
+            slots,
+            key: None,
+            label: String::new(),
+        })
+    }
+
+    /// Derives the key from the password, failing if it is the wrong password
+    pub fn unlock(&mut self, password: &[u8]) -> Result<(), Error> {
+        let key = derive_key(password, &self.salt, &self.params)?;
+        if unseal(&key, &self.check, CHECK).is_err() {
+            return Err(KeystoreError::WrongPassword.into());
+        }
+        self.key = Some(key);
+        Ok(())
+    }
+
+    /// Forgets the derived key
+    pub fn lock(&mut self) {
+        self.key = None;
+    }
+
+    /// Whether the keystore is locked
+    pub fn is_locked(&self) -> bool {
+        self.key.is_none()
+    }
+
+    /// Sets the plog label the [CryptoManager] impl stores keys under
+    pub fn select(&mut self, label: impl Into<String>) -> &mut Self {
+        self.label = label.into();
+        self
+    }
+
+    /// The plog label the [CryptoManager] impl stores keys under
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// All of the stored slots, as (label, key-path) pairs
+    pub fn list(&self) -> Vec<(String, String)> {
+        self.slots.keys().cloned().collect()
+    }
+
+    /// Whether a key is stored for the label and key-path
+    pub fn contains(&self, label: &str, key_path: &str) -> bool {
+        self.slots
+            .contains_key(&(label.to_string(), key_path.to_string()))
+    }
+
+    /// The secret key stored for the label and key-path
+    pub fn get(&self, label: &str, key_path: &str) -> Result<Multikey, Error> {
+        let key = self.key.as_ref().ok_or(CryptoError::KeystoreLocked)?;
+        let sealed = self
+            .slots
+            .get(&(label.to_string(), key_path.to_string()))
+            .ok_or_else(|| CryptoError::KeyNotFound(format!("{label}{key_path}")))?;
+        let bytes = unseal(key, sealed, &slot_aad(label, key_path))?;
+        Ok(Multikey::try_from(bytes.as_slice())?)
+    }
+
+    /// Seals the secret key under the label and key-path, replacing any stored key,
+    /// and saves the file
+    pub fn insert(&mut self, label: &str, key_path: &str, mk: &Multikey) -> Result<(), Error> {
+        self.seal_slot(label, key_path, mk)?;
+        self.save()
+    }
+
+    /// The stored secret key, multibase encoded for backup
+    pub fn export(&self, label: &str, key_path: &str) -> Result<Zeroizing<String>, Error> {
+        let mk = self.get(label, key_path)?;
+        Ok(Zeroizing::new(
+            EncodedMultikey::new(Base::Base58Btc, mk).to_string(),
+        ))
+    }
+
+    /// Deletes the key stored for the label and key-path and saves the file
+    ///
+    /// Returns false if there was no such key.
+    pub fn delete(&mut self, label: &str, key_path: &str) -> Result<bool, Error> {
+        if self.is_locked() {
+            return Err(CryptoError::KeystoreLocked.into());
+        }
+        let removed = self
+            .slots
+            .remove(&(label.to_string(), key_path.to_string()))
+            .is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Removes every key stored for the label, returning how many were removed
+    ///
+    /// Unlike [Keystore::delete] the file is not saved, the keys are only gone from
+    /// it after the next [Keystore::save].
+    pub fn remove_label(&mut self, label: &str) -> Result<usize, Error> {
+        if self.is_locked() {
+            return Err(CryptoError::KeystoreLocked.into());
+        }
+        let count = self.slots.len();
+        self.slots.retain(|(stored, _), _| stored != label);
+        Ok(count - self.slots.len())
+    }
+
+    /// Writes the keystore to its file, replacing the old file only once it is written
+    ///
+    /// Keys generated through the [CryptoManager] impl are only kept in memory until
+    /// this is called, so save once the plog they went into is stored. On unix the
+    /// file is only readable and writable by its owner.
+    pub fn save(&self) -> Result<(), Error> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        write_private(Path::new(&tmp), &self.encode())
+            .map_err(|e| KeystoreError::Io(e.to_string()))?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| KeystoreError::Io(e.to_string()))?;
+        Ok(())
+    }
+
+    fn seal_slot(&mut self, label: &str, key_path: &str, mk: &Multikey) -> Result<(), Error> {
+        let key = self.key.as_ref().ok_or(CryptoError::KeystoreLocked)?;
+        let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(mk.clone().into());
+        let sealed = seal(key, &bytes, &slot_aad(label, key_path))?;
+        self.slots
+            .insert((label.to_string(), key_path.to_string()), sealed);
+        Ok(())
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut v = MAGIC.to_vec();
+        v.append(&mut Varuint(KEYSTORE_VERSION).encode_into());
+        for cost in [
+            self.params.m_cost(),
+            self.params.t_cost(),
+            self.params.p_cost(),
+        ] {
+            v.append(&mut Varuint(u64::from(cost)).encode_into());
+        }
+        v.append(&mut Varbytes(self.salt.to_vec()).encode_into());
+        encode_sealed(&mut v, &self.check);
+        v.append(&mut Varuint(self.slots.len()).encode_into());
+        for ((label, key_path), sealed) in &self.slots {
+            v.append(&mut Varbytes(label.as_bytes().to_vec()).encode_into());
+            v.append(&mut Varbytes(key_path.as_bytes().to_vec()).encode_into());
+            encode_sealed(&mut v, sealed);
+        }
+        v
+    }
+}
+
+impl std::fmt::Debug for Keystore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keystore")
+            .field("path", &self.path)
+            .field("slots", &self.slots.keys().collect::<Vec<_>>())
+            .field("locked", &self.is_locked())
+            .field("label", &self.label)
+            .finish()
+    }
+}
+
+impl CryptoManager for Keystore {
+    fn get_mk(
+        &mut self,
+        key: &Key,
+        codec: Codec,
+        _threshold: usize,
+        _limit: usize,
+    ) -> Result<Multikey, Error> {
+        if self.is_locked() {
+            return Err(CryptoError::KeystoreLocked.into());
+        }
+        let mk = mk::Builder::new_from_random_bytes(codec, &mut OsRng)
+            .and_then(|builder| builder.try_build())
+            .map_err(|e| Error::from(e).or_unsupported_codec(codec))?;
+        let key_path = key.as_str();
+        if key_path != DEFAULT_ENTRYKEY && key_path != DEFAULT_VLAD_KEY {
+            let label = self.label.clone();
+            self.seal_slot(&label, key_path, &mk)?;
+        }
+        Ok(mk)
+    }
+
+    fn prove(&self, mk: &Multikey, data: &[u8]) -> Result<Multisig, Error> {
+        Ok(mk.sign_view()?.sign(data, false, None)?)
+    }
+}
+
+/// Writes a new file that, on unix, only its owner can read and write
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    // a left over file may have other permissions, start from a new one
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// The associated data binding a sealed key to its slot
+fn slot_aad(label: &str, key_path: &str) -> Vec<u8> {
+    let mut aad = Varbytes(label.as_bytes().to_vec()).encode_into();
+    aad.append(&mut Varbytes(key_path.as_bytes().to_vec()).encode_into());
+    aad
+}
+
+fn kdf_params(m_cost: u32, t_cost: u32, p_cost: u32) -> Result<Params, KeystoreError> {
+    Params::new(m_cost, t_cost, p_cost, Some(32)).map_err(|e| KeystoreError::Crypto(e.to_string()))
+}
+
+fn derive_key(
+    password: &[u8],
+    salt: &[u8],
+    params: &Params,
+) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+        .hash_password_into(password, salt, key.as_mut())
+        .map_err(|e| KeystoreError::Crypto(e.to_string()))?;
+    Ok(key)
+}
+
+fn seal(key: &[u8; 32], msg: &[u8], aad: &[u8]) -> Result<Sealed, KeystoreError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg, aad })
+        .map_err(|e| KeystoreError::Crypto(e.to_string()))?;
+    Ok(Sealed { nonce, ciphertext })
+}
+
+fn unseal(
+    key: &[u8; 32],
+    sealed: &Sealed,
+    aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, KeystoreError> {
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(
+            XNonce::from_slice(&sealed.nonce),
+            Payload {
+                msg: &sealed.ciphertext,
+                aad,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|e| KeystoreError::Crypto(e.to_string()))
+}
+
+fn encode_sealed(out: &mut Vec<u8>, sealed: &Sealed) {
+    out.append(&mut Varbytes(sealed.nonce.to_vec()).encode_into());
+    out.append(&mut Varbytes(sealed.ciphertext.clone()).encode_into());
+}
+
+fn decode_sealed(bytes: &[u8]) -> Result<(Sealed, &[u8]), Error> {
+    let (nonce, rest) = Varbytes::try_decode_from(bytes)?;
+    let nonce = nonce
+        .to_inner()
+        .try_into()
+        .map_err(|_| KeystoreError::InvalidFile("nonce length"))?;
+    let (ciphertext, rest) = Varbytes::try_decode_from(rest)?;
+    Ok((
+        Sealed {
+            nonce,
+            ciphertext: ciphertext.to_inner(),
+        },
+        rest,
+    ))
+}
+
+type Decoded = (
+    Params,
+    [u8; SALT_LEN],
+    Sealed,
+    BTreeMap<(String, String), Sealed>,
+);
+
+/// Decodes an Argon2id cost
+fn decode_cost(bytes: &[u8]) -> Result<(u32, &[u8]), Error> {
+    let (cost, rest) = Varuint::<u64>::try_decode_from(bytes)?;
+    let cost = u32::try_from(cost.to_inner())
+        .map_err(|_| KeystoreError::InvalidFile("argon2 parameter too large"))?;
+    Ok((cost, rest))
+}
+
+fn decode(bytes: &[u8]) -> Result<Decoded, Error> {
+    let rest = bytes
+        .strip_prefix(MAGIC.as_slice())
+        .ok_or(KeystoreError::InvalidFile("not a keystore"))?;
+    let (version, rest) = Varuint::<u64>::try_decode_from(rest)?;
+    let (params, rest) = match version.to_inner() {
+        1 => (kdf_params(KDF_M_COST, KDF_T_COST, KDF_P_COST)?, rest),
+        KEYSTORE_VERSION => {
+            let (m_cost, rest) = decode_cost(rest)?;
+            let (t_cost, rest) = decode_cost(rest)?;
+            let (p_cost, rest) = decode_cost(rest)?;
+            let params = kdf_params(m_cost, t_cost, p_cost)
+                .map_err(|_| KeystoreError::InvalidFile("argon2 parameters"))?;
+            (params, rest)
+        }
+        _ => return Err(KeystoreError::InvalidFile("unsupported version").into()),
+    };
+    let (salt, rest) = Varbytes::try_decode_from(rest)?;
+    let salt = salt
+        .to_inner()
+        .try_into()
+        .map_err(|_| KeystoreError::InvalidFile("salt length"))?;
+    let (check, rest) = decode_sealed(rest)?;
+    let (count, mut rest) = Varuint::<usize>::try_decode_from(rest)?;
+    let mut slots = BTreeMap::new();
+    for _ in 0..count.to_inner() {
+        let (label, next) = Varbytes::try_decode_from(rest)?;
+        let (key_path, next) = Varbytes::try_decode_from(next)?;
+        let (sealed, next) = decode_sealed(next)?;
+        let label = String::from_utf8(label.to_inner())
+            .map_err(|_| KeystoreError::InvalidFile("label is not utf-8"))?;
+        let key_path = String::from_utf8(key_path.to_inner())
+            .map_err(|_| KeystoreError::InvalidFile("key-path is not utf-8"))?;
+        slots.insert((label, key_path), sealed);
+        rest = next;
+    }
+    if !rest.is_empty() {
+        return Err(KeystoreError::InvalidFile("trailing bytes").into());
+    }
+    Ok((params, salt, check, slots))
+}
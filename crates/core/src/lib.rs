@@ -19,6 +19,52 @@ pub use multitrait::Null;
 
 pub mod utils;
 
+/// The versioned plog interchange format
+pub mod envelope;
+
+/// Lossless DAG-JSON export and import of plogs
+#[cfg(feature = "serde")]
+pub mod json;
+
+/// Equivocation (fork) fraud proofs
+pub mod equivocation;
+pub use equivocation::EquivocationProof;
+
+/// Verified checkpoints of plog state
+pub mod checkpoint;
+pub use checkpoint::Checkpoint;
+
+/// Per-entry verification reports
+pub mod report;
+pub use report::VerificationReport;
+
+/// Time travel queries over plog state
+pub mod history;
+
+/// Differences between plog states
+pub mod diff;
+
+/// Lipmaa inclusion proofs and key certificates
+pub mod inclusion;
+pub use inclusion::{InclusionProof, KeyCertificate};
+
+/// Detached document signatures anchored to a plog
+pub mod document;
+pub use document::DetachedSignature;
+
+/// `did:plog` DIDs and DID Documents
+pub mod did;
+
+/// Deterministic key derivation per key-path
+#[cfg(feature = "derive")]
+pub mod derive;
+
+/// Encrypted on-disk keystore
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(feature = "keystore")]
+pub use keystore::Keystore;
+
 /// Resolving utilities
 pub mod resolve;
 
@@ -26,4 +72,12 @@ pub mod resolve;
 #[cfg(feature = "blockstore")]
 pub use resolve::blockstore_resolver;
 
+/// If [fs] feature is enabled, export the directory backed block store
+#[cfg(feature = "fs")]
+pub use resolve::fs_resolver;
+
+/// If [http] feature is enabled, export the trustless HTTP gateway resolver
+#[cfg(feature = "http")]
+pub use resolve::http_resolver;
+
 pub use multikey::multicrates::*;
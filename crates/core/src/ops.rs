@@ -1,16 +1,21 @@
 /// Create a new provenance log
 pub mod open;
 pub use open::create;
+#[cfg(feature = "derive")]
+pub use open::create_from_seed;
 
 /// Update a provenance log
 pub mod update;
-pub use update::update_plog;
+pub use update::{update_plog, update_plog_from};
 
 pub mod config;
 
 mod traits;
 pub use traits::CryptoManager;
 
+mod context;
+pub use context::{SigningContext, SigningPurpose};
+
 /// Handy export for all public symbols
 pub mod prelude {
     pub use super::*;
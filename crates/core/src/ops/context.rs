@@ -0,0 +1,68 @@
+use provenance_log::multicid;
+
+use multicid::{Cid, Vlad};
+use provenance_log::{Entry, Key};
+
+use crate::{utils::EntryDisplay, utils::OpDisplay, Error};
+
+/// What a [CryptoManager](super::CryptoManager) is asked to sign
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SigningPurpose {
+    /// The [Cid] of the first lock script, to bind a new [Vlad] to it
+    Vlad {
+        /// The [Cid] the [Vlad] is built from
+        cid: Cid,
+    },
+    /// The first entry of a new plog
+    FirstEntry,
+    /// An entry appended to an existing plog
+    Update,
+    /// An external document, signed on behalf of the plog
+    Document,
+}
+
+/// Describes the data passed to [CryptoManager::prove_with_context](super::CryptoManager::prove_with_context),
+/// so a wallet can show a confirmation and enforce its policy before signing
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SigningContext {
+    /// What is being signed
+    pub purpose: SigningPurpose,
+    /// The plog being signed for, none while its [Vlad] is being built
+    pub vlad: Option<Vlad>,
+    /// The seqno of the entry being signed, or of the entry a document is signed at
+    pub seqno: Option<u64>,
+    /// The key-path of the signing key, if it is known
+    pub key_path: Option<Key>,
+    /// The decoded ops of the entry being signed, in order
+    pub ops: Vec<OpDisplay>,
+}
+
+impl SigningContext {
+    /// The context for signing the [Cid] a new [Vlad] is built from
+    pub fn vlad(cid: &Cid, key_path: &Key) -> Self {
+        Self {
+            purpose: SigningPurpose::Vlad { cid: cid.clone() },
+            vlad: None,
+            seqno: None,
+            key_path: Some(key_path.clone()),
+            ops: Vec::new(),
+        }
+    }
+
+    /// The context for signing the [Entry], summarizing its ops
+    pub fn entry(
+        purpose: SigningPurpose,
+        entry: &Entry,
+        key_path: Option<Key>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            purpose,
+            vlad: Some(entry.vlad()),
+            seqno: Some(entry.seqno()),
+            key_path,
+            ops: EntryDisplay::new(entry)?.ops,
+        })
+    }
+}
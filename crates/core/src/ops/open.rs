@@ -16,7 +16,7 @@ use provenance_log::{entry, error::EntryError, Error as PlogError, Log};
 
 use crate::ops::update::OpParams;
 
-use super::traits::CryptoManager;
+use super::{traits::CryptoManager, SigningContext, SigningPurpose};
 
 pub fn create(config: &Config, key_manager: &mut impl CryptoManager) -> Result<Log, crate::Error> {
     // 0. Set up the list of ops we're going to add
@@ -36,7 +36,11 @@ pub fn create(config: &Config, key_manager: &mut impl CryptoManager) -> Result<L
             // call back to generate the key
             let mk = key_manager_ref
                 .borrow_mut()
-                .get_mk(key, *codec, *threshold, *limit)?;
+                .get_mk(key, *codec, *threshold, *limit)
+                .map_err(|e| Error::KeyGeneration {
+                    key: key.to_string(),
+                    source: Box::new(e),
+                })?;
 
             // get the public key
             let pk = if mk.attr_view()?.is_secret_key() {
@@ -136,14 +140,26 @@ pub fn create(config: &Config, key_manager: &mut impl CryptoManager) -> Result<L
     let vlad_mk = load_key(vlad_key_params)?;
     let vlad_cid = load_cid(vlad_cid_params)?;
 
+    // the typed error of a failed proof, as the builders only keep its message
+    let prove_error = RefCell::new(None);
+
     // construct the signed vlad using the vlad pubkey and the first lock script cid
+    let vlad_context = SigningContext::vlad(&vlad_cid, vlad_key_params.key());
     let vlad = vlad::Builder::default()
         .with_cid(&vlad_cid)
         .try_build(|cid| {
             let cv: Vec<u8> = cid.clone().into();
-            let ms = key_manager_ref.borrow().prove(&vlad_mk, &cv)?;
+            let ms = key_manager_ref
+                .borrow()
+                .prove_with_context(&vlad_mk, &cv, &vlad_context)
+                .map_err(|e| {
+                    let err = multicid::Error::from(Error::Generic(e.to_string()));
+                    *prove_error.borrow_mut() = Some(Error::VladSigning(Box::new(e)));
+                    err
+                })?;
             Ok(ms.into())
-        })?;
+        })
+        .map_err(|e| prove_error.take().unwrap_or(e.into()))?;
 
     // drop the vlad_mk to Zeroize the key
     drop(vlad_mk);
@@ -183,17 +199,29 @@ pub fn create(config: &Config, key_manager: &mut impl CryptoManager) -> Result<L
         })?;
 
     // finalize the entry building by signing it
-    let entry = builder.try_build(|e| {
-        // get the serialzied version of the entry with an empty "proof" field
-        let ev: Vec<u8> = e.clone().into();
-        // call the call back to have the caller sign the data
-        let ms = key_manager_ref
-            .borrow()
-            .prove(&entry_mk, &ev)
+    let entry = builder
+        .try_build(|e| {
+            // get the serialzied version of the entry with an empty "proof" field
+            let ev: Vec<u8> = e.clone().into();
+            let context = SigningContext::entry(
+                SigningPurpose::FirstEntry,
+                e,
+                Some(entrykey_params.key().clone()),
+            )
             .map_err(|e| PlogError::from(EntryError::SignFailed(e.to_string())))?;
-        // store the signature as proof
-        Ok(ms.into())
-    })?;
+            // call the call back to have the caller sign the data
+            let ms = key_manager_ref
+                .borrow()
+                .prove_with_context(&entry_mk, &ev, &context)
+                .map_err(|e| {
+                    let err = PlogError::from(EntryError::SignFailed(e.to_string()));
+                    *prove_error.borrow_mut() = Some(Error::EntrySigning(Box::new(e)));
+                    err
+                })?;
+            // store the signature as proof
+            Ok(ms.into())
+        })
+        .map_err(|e| prove_error.take().unwrap_or(e.into()))?;
 
     // securely destroy the entry_mk
     drop(entry_mk);
@@ -209,6 +237,20 @@ pub fn create(config: &Config, key_manager: &mut impl CryptoManager) -> Result<L
     Ok(log)
 }
 
+/// Creates a new plog with every key derived from the seed
+///
+/// Identical configs and seeds give byte-identical logs, see [crate::derive]. The
+/// returned key manager holds the derived keys, to sign updates with.
+#[cfg(feature = "derive")]
+pub fn create_from_seed(
+    config: &Config,
+    seed: &[u8],
+) -> Result<(Log, crate::derive::SeededKeyManager), crate::Error> {
+    let mut key_manager = crate::derive::SeededKeyManager::new(seed);
+    let log = create(config, &mut key_manager)?;
+    Ok((log, key_manager))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ops::open::config::NewLogBuilder;
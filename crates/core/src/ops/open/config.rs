@@ -2,7 +2,9 @@ use crate::ops::config::defaults::*;
 use crate::ops::config::utils::*;
 use crate::ops::config::{LockScript, UnlockScript, VladConfig};
 
-use provenance_log::Script;
+use provenance_log::{multicodec, Script};
+
+use multicodec::Codec;
 
 use crate::ops::update::OpParams;
 
@@ -127,6 +129,31 @@ impl NewLogBuilder {
         self
     }
 
+    /// Set the codec of the Vlad key, keeping its other params
+    pub fn with_vlad_key_codec(&mut self, codec: Codec) -> &mut Self {
+        set_key_codec(&mut self.vlad_params.key.0, codec);
+        self
+    }
+
+    /// Set the codec of the entry key, keeping its other params
+    pub fn with_entrykey_codec(&mut self, codec: Codec) -> &mut Self {
+        set_key_codec(&mut self.entrykey_params, codec);
+        self
+    }
+
+    /// Set the codec of the pubkey, keeping its other params
+    pub fn with_pubkey_codec(&mut self, codec: Codec) -> &mut Self {
+        set_key_codec(&mut self.pubkey_params, codec);
+        self
+    }
+
+    /// Set the codec of the Vlad key, the entry key and the pubkey
+    pub fn with_key_codec(&mut self, codec: Codec) -> &mut Self {
+        self.with_vlad_key_codec(codec)
+            .with_entrykey_codec(codec)
+            .with_pubkey_codec(codec)
+    }
+
     /// Build the Config
     pub fn build(self) -> Config {
         Config {
@@ -140,3 +167,10 @@ impl NewLogBuilder {
         }
     }
 }
+
+/// Sets the codec of key generation params, other params are left alone
+fn set_key_codec(params: &mut OpParams, new_codec: Codec) {
+    if let OpParams::KeyGen { codec, .. } = params {
+        *codec = new_codec;
+    }
+}
@@ -5,9 +5,14 @@ use multikey::Multikey;
 use multisig::Multisig;
 use provenance_log::Key;
 
+use super::SigningContext;
 use crate::Error;
 
 /// Users implement this trait to provide the keys for the log
+///
+/// Failures of the key manager itself, such as a missing key or a locked keystore,
+/// should be returned as [CryptoError](crate::error::CryptoError)s, so callers can tell
+/// them apart. The ops wrap them in the stage that failed, see [Error::crypto].
 pub trait CryptoManager {
     /// Get a mulitkey for the requested [Key] path.
     ///
@@ -26,4 +31,19 @@ pub trait CryptoManager {
 
     /// Generates proof for this key, such as a signature, over the data.
     fn prove(&self, mk: &Multikey, data: &[u8]) -> Result<Multisig, Error>;
+
+    /// Generates proof like [prove](Self::prove), with the [SigningContext] saying
+    /// what the data is, so the implementation can ask the user to confirm.
+    ///
+    /// The ops always sign through this method. Return
+    /// [CryptoError::UserRejected](crate::error::CryptoError::UserRejected) if the user
+    /// declines. The default ignores the context and calls [prove](Self::prove).
+    fn prove_with_context(
+        &self,
+        mk: &Multikey,
+        data: &[u8],
+        _context: &SigningContext,
+    ) -> Result<Multisig, Error> {
+        self.prove(mk, data)
+    }
 }
@@ -16,9 +16,14 @@ use multikey::{Multikey, Views as _};
 pub use op_params::OpParams;
 use provenance_log::error::EntryError;
 use provenance_log::Lipmaa as _;
-use provenance_log::{entry, error::Error as PlogError, Log};
+use provenance_log::{entry, error::Error as PlogError, Entry, Key, Log};
 
-use crate::{error::OpenError, ops::traits::CryptoManager, utils, Error};
+use crate::{
+    checkpoint::Checkpoint,
+    error::{CheckpointError, OpenError},
+    ops::{traits::CryptoManager, SigningContext, SigningPurpose},
+    utils, Error,
+};
 
 /// Update the provenance log with a new entry(s) and additional ops
 pub fn update_plog(
@@ -26,6 +31,72 @@ pub fn update_plog(
     config: &UpdateConfig,
     key_manager: &mut impl CryptoManager,
 ) -> Result<(), crate::Error> {
+    // validate the p.log and get the last entry and state
+    let (_, last_entry, kvp) = plog
+        .verify()
+        .last()
+        .ok_or(crate::error::UpdateError::NoLastEntry)??;
+
+    let key_path = signing_key_path(utils::multikeys(kvp.iter()), &config.entry_signing_key);
+    append_entry(plog, &last_entry, config, key_path, key_manager)?;
+    Ok(())
+}
+
+/// Update the provenance log like [update_plog], but trust the [Checkpoint] of the
+/// head instead of verifying the whole log first.
+///
+/// The [Checkpoint] must be at the current head. Returns the [Checkpoint] of the new head.
+pub fn update_plog_from(
+    plog: &mut Log,
+    checkpoint: &Checkpoint,
+    config: &UpdateConfig,
+    key_manager: &mut impl CryptoManager,
+) -> Result<Checkpoint, crate::Error> {
+    if checkpoint.vlad != plog.vlad {
+        return Err(CheckpointError::VladMismatch.into());
+    }
+    if checkpoint.cid != plog.head {
+        return Err(CheckpointError::NotAtHead(checkpoint.seqno).into());
+    }
+    let last_entry = plog
+        .entries
+        .get(&plog.head)
+        .ok_or(crate::error::UpdateError::NoLastEntry)?
+        .clone();
+
+    let pairs = checkpoint
+        .state
+        .iter()
+        .map(|(key, value)| {
+            Key::try_from(key.as_str())
+                .map(|key| (key, value))
+                .map_err(|_| CheckpointError::InvalidKey(key.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let key_path = signing_key_path(
+        utils::multikeys(pairs.iter().map(|(key, value)| (key, *value))),
+        &config.entry_signing_key,
+    );
+    let entry = append_entry(plog, &last_entry, config, key_path, key_manager)?;
+    Ok(checkpoint.advance(&entry))
+}
+
+/// The key-path of the public key of the signing key among the keys of the state
+fn signing_key_path(keys: Vec<(Key, Multikey)>, signing_key: &Multikey) -> Option<Key> {
+    let public_key = signing_key.conv_view().ok()?.to_public_key().ok()?;
+    keys.into_iter()
+        .find(|(_, mk)| *mk == public_key)
+        .map(|(key, _)| key)
+}
+
+/// Builds, signs and appends the next entry after `last_entry`, returning the new entry
+fn append_entry(
+    plog: &mut Log,
+    last_entry: &Entry,
+    config: &UpdateConfig,
+    key_path: Option<Key>,
+    key_manager: &mut impl CryptoManager,
+) -> Result<Entry, crate::Error> {
     // 0. Set up the list of ops we're going to add
     let op_params = RefCell::new(Vec::default());
 
@@ -43,7 +114,11 @@ pub fn update_plog(
             // call back to generate the key
             let mk = key_manager_ref
                 .borrow_mut()
-                .get_mk(key, *codec, *threshold, *limit)?;
+                .get_mk(key, *codec, *threshold, *limit)
+                .map_err(|e| Error::KeyGeneration {
+                    key: key.to_string(),
+                    source: Box::new(e),
+                })?;
 
             // get the public key
             let pk = mk.conv_view()?.to_public_key()?;
@@ -130,17 +205,11 @@ pub fn update_plog(
         };
     }
 
-    // 1. validate the p.log and get the last entry and state
-    let (_, last_entry, _kvp) = plog
-        .verify()
-        .last()
-        .ok_or(crate::error::UpdateError::NoLastEntry)??;
-
     let unlock_script = config.entry_unlock_script.clone();
     let entry_mk = config.entry_signing_key.clone();
 
     // construct the first entry from all of the parts
-    let mut builder = entry::Builder::from(&last_entry).with_unlock(&unlock_script);
+    let mut builder = entry::Builder::from(last_entry).with_unlock(&unlock_script);
 
     for (_key_path, lock) in &config.add_entry_lock_scripts {
         builder = builder.add_lock(lock);
@@ -166,18 +235,29 @@ pub fn update_plog(
         tracing::trace!("No lipmaa for seqno: {}", curr_seqno);
     }
 
+    // the typed error of a failed proof, as the builder only keeps its message
+    let prove_error = RefCell::new(None);
+
     // finalize the entry building by signing it
-    let entry = builder.try_build(|e| {
-        // get the serialzied version of the entry with an empty "proof" field
-        let ev: Vec<u8> = e.clone().into();
-        // call the call back to have the caller sign the data
-        let ms = key_manager_ref
-            .borrow()
-            .prove(&entry_mk, &ev)
-            .map_err(|e| PlogError::from(EntryError::SignFailed(e.to_string())))?;
-        // store the signature as proof
-        Ok(ms.into())
-    })?;
+    let entry = builder
+        .try_build(|e| {
+            // get the serialzied version of the entry with an empty "proof" field
+            let ev: Vec<u8> = e.clone().into();
+            let context = SigningContext::entry(SigningPurpose::Update, e, key_path.clone())
+                .map_err(|e| PlogError::from(EntryError::SignFailed(e.to_string())))?;
+            // call the call back to have the caller sign the data
+            let ms = key_manager_ref
+                .borrow()
+                .prove_with_context(&entry_mk, &ev, &context)
+                .map_err(|e| {
+                    let err = PlogError::from(EntryError::SignFailed(e.to_string()));
+                    *prove_error.borrow_mut() = Some(Error::EntrySigning(Box::new(e)));
+                    err
+                })?;
+            // store the signature as proof
+            Ok(ms.into())
+        })
+        .map_err(|e| prove_error.take().unwrap_or(e.into()))?;
 
     // try to add the entry to the p.log
     plog.try_append(entry.clone())?;
@@ -191,7 +271,7 @@ pub fn update_plog(
         plog.entries.get(&plog.head).unwrap().prev()
     );
 
-    Ok(())
+    Ok(entry)
 }
 
 #[cfg(test)]
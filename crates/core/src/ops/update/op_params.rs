@@ -84,6 +84,22 @@ pub enum OpParams {
     },
 }
 
+impl OpParams {
+    /// The key-path the op params are for
+    pub fn key(&self) -> &Key {
+        match self {
+            Self::Noop { key }
+            | Self::Delete { key }
+            | Self::CidGen { key, .. }
+            | Self::KeyGen { key, .. }
+            | Self::UseCid { key, .. }
+            | Self::UseKey { key, .. }
+            | Self::UseStr { key, .. }
+            | Self::UseBin { key, .. } => key,
+        }
+    }
+}
+
 impl Default for OpParams {
     fn default() -> Self {
         Self::Noop {
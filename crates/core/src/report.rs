@@ -0,0 +1,152 @@
+//! Per-entry verification reports.
+//!
+//! [Log::verify] stops at the first entry that fails, and the error alone does not
+//! say which entry that was. A [VerificationReport] lines the verifier results up
+//! with the entries in seqno order, so a UI can show exactly where a log breaks.
+
+use provenance_log::multicid;
+
+use multicid::{Cid, Vlad};
+use provenance_log::{Entry, Log, Op, Script};
+
+/// The verification status of one entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EntryStatus {
+    /// The entry verified
+    Verified,
+    /// The entry failed to verify
+    Failed,
+    /// The entry comes after a failed entry, so it was not verified
+    Unverified,
+}
+
+/// The verification result for one entry
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryReport {
+    /// The seqno of the entry
+    pub seqno: u64,
+    /// The [Cid] of the entry
+    pub cid: Cid,
+    /// Whether the entry verified
+    pub status: EntryStatus,
+    /// The verification count of the entry, if it verified
+    pub count: Option<usize>,
+    /// The key-path of the lock script that most likely governed the entry, only set
+    /// for verified entries. This is a heuristic, the longest lock path of the
+    /// previous entry that is a parent of every op in the entry, not the lock the
+    /// verifier found satisfied, which provenance-log does not report.
+    pub candidate_lock: Option<String>,
+    /// Why the entry failed, if it did
+    pub error: Option<String>,
+}
+
+/// The verification results for every entry of a [Log], from foot to head
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VerificationReport {
+    /// The plog the report is for
+    pub vlad: Vlad,
+    /// The head of the verified log
+    pub head: Cid,
+    /// One report per entry, in seqno order
+    pub entries: Vec<EntryReport>,
+}
+
+impl VerificationReport {
+    /// Verifies the [Log] and reports on every entry
+    pub fn new(log: &Log) -> Self {
+        let mut by_seqno: Vec<&Entry> = log.entries.values().collect();
+        by_seqno.sort_by_key(|entry| entry.seqno());
+
+        let mut results = log.verify();
+        let mut failed = false;
+        let mut prev: Option<&Entry> = None;
+        let mut entries = Vec::with_capacity(by_seqno.len());
+
+        for entry in by_seqno {
+            let next = if failed { None } else { results.next() };
+            let (status, count, error) = match (failed, next) {
+                (false, Some(Ok((count, _, _)))) => (EntryStatus::Verified, Some(count), None),
+                (false, Some(Err(e))) => {
+                    failed = true;
+                    (EntryStatus::Failed, None, Some(e.to_string()))
+                }
+                (false, None) => {
+                    failed = true;
+                    (
+                        EntryStatus::Failed,
+                        None,
+                        Some("the verifier stopped before this entry".to_string()),
+                    )
+                }
+                (true, _) => (EntryStatus::Unverified, None, None),
+            };
+            let candidate_lock = match status {
+                EntryStatus::Verified => candidate_lock(log, prev, entry),
+                _ => None,
+            };
+            entries.push(EntryReport {
+                seqno: entry.seqno(),
+                cid: entry.cid(),
+                status,
+                count,
+                candidate_lock,
+                error,
+            });
+            prev = Some(entry);
+        }
+
+        Self {
+            vlad: log.vlad.clone(),
+            head: log.head.clone(),
+            entries,
+        }
+    }
+
+    /// Returns true if every entry verified
+    pub fn is_valid(&self) -> bool {
+        !self.entries.is_empty()
+            && self
+                .entries
+                .iter()
+                .all(|entry| entry.status == EntryStatus::Verified)
+    }
+
+    /// The first entry that failed, if any
+    pub fn first_failure(&self) -> Option<&EntryReport> {
+        self.entries
+            .iter()
+            .find(|entry| entry.status == EntryStatus::Failed)
+    }
+
+    /// The total verification count of the verified entries
+    pub fn total_count(&self) -> usize {
+        self.entries.iter().filter_map(|entry| entry.count).sum()
+    }
+}
+
+/// A guess at the key-path of the lock that governed the [Entry].
+///
+/// The first entry is governed by the first lock of the log, later entries by the
+/// locks of the entry before them. The most specific lock whose path is a parent of
+/// every op path is picked, whether or not its script is the one that passed.
+fn candidate_lock(log: &Log, prev: Option<&Entry>, entry: &Entry) -> Option<String> {
+    let locks: Vec<Script> = match prev {
+        Some(prev) => prev.locks().cloned().collect(),
+        None => vec![log.first_lock.clone()],
+    };
+    let op_paths: Vec<String> = entry
+        .ops()
+        .map(|op| match op {
+            Op::Noop(key) | Op::Delete(key) | Op::Update(key, _) => key.to_string(),
+        })
+        .collect();
+
+    locks
+        .iter()
+        .map(|lock| lock.path().to_string())
+        .filter(|path| op_paths.iter().all(|op_path| op_path.starts_with(path)))
+        .max_by_key(|path| path.len())
+}
@@ -11,17 +11,33 @@
 //! Users can implement the [Resolver] trait to define how to resolve the data
 //! from a CID chain. Then, the [get_entry_chain] function can be used to get
 //! the entries from the head CID down to the foot CID.
+//!
+//! The other direction, storing the blocks of a plog so that others can resolve
+//! them, is covered by the [Publisher] trait and [publish_plog].
 
 #[cfg(feature = "blockstore")]
 pub mod blockstore_resolver;
 
+#[cfg(feature = "fs")]
+pub mod fs_resolver;
+
+#[cfg(feature = "http")]
+pub mod http_resolver;
+
+pub mod naming;
+pub use naming::{resolve_by_vlad, HeadResolver};
+
+pub mod fork_choice;
+pub use fork_choice::{choose_head, ForkChoice, ForkChoicePolicy};
+
 use provenance_log::{multicid, multicodec, multihash, multitrait, multiutil};
 
+use crate::report::VerificationReport;
 use indexmap::IndexMap;
 use multicid::Cid;
 use multitrait::Null;
 use multiutil::CodecInfo;
-use provenance_log::Entry;
+use provenance_log::{Entry, Key, Log, Script};
 use std::{cmp::Ordering, future::Future, pin::Pin};
 
 /// Error types for resolution operations
@@ -42,6 +58,9 @@ pub enum ResolveError {
     #[error("Failed to get last entry")]
     NoLastEntry,
 
+    #[error("No candidate head resolved to a valid log")]
+    NoValidHead,
+
     #[error("Other error: {0}")]
     Other(#[from] Box<dyn std::error::Error>),
 }
@@ -101,6 +120,99 @@ pub trait Resolver {
     ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::Error>> + Send>>;
 }
 
+/// A trait for storing data under its [Cid], the counterpart of [Resolver].
+///
+/// Implementations are free to verify the data against the [Cid] before storing it,
+/// but resolvers must not rely on it: [get_entry_chain] rechecks every block it fetches.
+#[allow(clippy::type_complexity)]
+pub trait Publisher {
+    type Error: std::error::Error + 'static;
+
+    fn publish(
+        &self,
+        cid: &Cid,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send>>;
+}
+
+/// Checks that the given bytes hash to the given [Cid].
+pub fn verify_block(cid: &Cid, data: &[u8]) -> Result<(), ResolveError> {
+    let rebuilt_cid = multicid::cid::Builder::new(multicodec::Codec::Cidv1)
+        .with_target_codec(cid.target_codec)
+        .with_hash(
+            &multihash::Builder::new_from_bytes(cid.hash.codec(), data)
+                .map_err(|e| ResolveError::Other(Box::new(e)))?
+                .try_build()
+                .map_err(|e| ResolveError::Other(Box::new(e)))?,
+        )
+        .try_build()
+        .map_err(|e| ResolveError::Other(Box::new(e)))?;
+
+    // error if the Cids don't match
+    if &rebuilt_cid != cid {
+        return Err(ResolveError::CidMismatch {
+            expected: cid.clone(),
+            actual: rebuilt_cid,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the content addressed blocks that make up a [Log]:
+/// the first lock script under the [Vlad](multicid::Vlad) [Cid], followed by every [Entry] under its [Cid].
+///
+/// A [Script::Code] first lock is stored as its source text, which is what the Vlad [Cid]
+/// is generated over (see [DEFAULT_FIRST_LOCK_SCRIPT](crate::ops::config::defaults::DEFAULT_FIRST_LOCK_SCRIPT)).
+pub fn plog_blocks(log: &Log) -> Vec<(Cid, Vec<u8>)> {
+    let first_lock_bytes: Vec<u8> = match &log.first_lock {
+        Script::Code(_, code) => code.as_bytes().to_vec(),
+        script => script.clone().into(),
+    };
+
+    let mut blocks = vec![(log.vlad.cid().clone(), first_lock_bytes)];
+    blocks.extend(log.entries.iter().map(|(cid, entry)| {
+        let entry_bytes: Vec<u8> = entry.clone().into();
+        (cid.clone(), entry_bytes)
+    }));
+    blocks
+}
+
+/// Publishes all of the blocks of the [Log] using the [Publisher], so that the
+/// [Log] can later be rebuilt with [resolve_plog].
+pub async fn publish_plog(log: &Log, publisher: &impl Publisher) -> Result<(), ResolveError> {
+    for (cid, data) in plog_blocks(log) {
+        publisher
+            .publish(&cid, data)
+            .await
+            .map_err(|e| ResolveError::Other(Box::new(e)))?;
+    }
+    Ok(())
+}
+
+/// Parses the first lock [Script] from the bytes resolved for the [Vlad](multicid::Vlad) [Cid].
+///
+/// The [Cid] is over the source text of the lock script. The bytes are accepted as
+/// that text, as stored by [plog_blocks], or as an encoded [Script::Code] whose source
+/// text matches the [Cid], as stored by earlier publishers.
+fn first_lock_from_bytes(cid: &Cid, bytes: &[u8]) -> Result<Script, ResolveError> {
+    if let Err(e) = verify_block(cid, bytes) {
+        return match Script::try_from(bytes) {
+            Ok(Script::Code(path, code)) if verify_block(cid, code.as_bytes()).is_ok() => {
+                Ok(Script::Code(path, code))
+            }
+            _ => Err(e),
+        };
+    }
+    match Script::try_from(bytes) {
+        Ok(script) => Ok(script),
+        Err(e) => match std::str::from_utf8(bytes) {
+            Ok(code) => Ok(Script::Code(Key::default(), code.to_string())),
+            Err(_) => Err(ResolveError::Other(Box::new(e))),
+        },
+    }
+}
+
 /// Recursively get the resolved data from a head [Cid] down to the foot [Cid],
 /// returning the list of [Entry]s for the [Log]. Uses the [Resolver] to fetch
 /// the data.
@@ -119,24 +231,7 @@ pub async fn get_entry_chain(
             .map_err(|e| ResolveError::Other(Box::new(e)))?;
 
         // entry bytes Cid should match the given Cid
-        let rebuilt_cid = multicid::cid::Builder::new(multicodec::Codec::Cidv1)
-            .with_target_codec(current_cid.target_codec)
-            .with_hash(
-                &multihash::Builder::new_from_bytes(current_cid.hash.codec(), &entry_bytes)
-                    .map_err(|e| ResolveError::Other(Box::new(e)))?
-                    .try_build()
-                    .map_err(|e| ResolveError::Other(Box::new(e)))?,
-            )
-            .try_build()
-            .map_err(|e| ResolveError::Other(Box::new(e)))?;
-
-        // error if the Cids don't match
-        if rebuilt_cid != current_cid {
-            return Err(ResolveError::CidMismatch {
-                expected: current_cid,
-                actual: rebuilt_cid,
-            });
-        }
+        verify_block(&current_cid, &entry_bytes)?;
 
         let entry = Entry::try_from(entry_bytes.as_slice())
             .map_err(|e| ResolveError::Other(Box::new(e)))?;
@@ -213,18 +308,69 @@ pub async fn resolve_plog(
     head_cid: &multicid::Cid,
     resolver: impl Resolver + Clone,
 ) -> Result<ResolvedPlog, ResolveError> {
+    let rebuilt_plog = rebuild_plog(vlad, head_cid, resolver).await?;
+
+    let plog_clone = rebuilt_plog.clone();
+
+    let verify_iter = &mut plog_clone.verify();
+
+    // Collect individual verification counts
+    let mut verification_counts = Vec::new();
+
+    // the log should also verify
+    for ret in verify_iter {
+        match ret {
+            Ok((count, entry, kvp)) => {
+                verification_counts.push(count);
+                tracing::trace!("Verified entry: {:#?}", entry);
+                tracing::trace!("Verified count: {:#?}", count);
+                tracing::trace!("Verified kvp: {:#?}", kvp);
+            }
+            Err(e) => {
+                tracing::error!("Error: {:#?}", e);
+                return Err(ResolveError::VerificationError(e.to_string()));
+            }
+        }
+    }
+
+    Ok(ResolvedPlog {
+        log: rebuilt_plog,
+        verification_counts,
+    })
+}
+
+/// Like [resolve_plog], but instead of stopping at the first verification error,
+/// returns the rebuilt [Log] along with a [VerificationReport] for every entry.
+///
+/// Only failures to fetch or rebuild the log are errors.
+pub async fn resolve_plog_report(
+    vlad: &multicid::Vlad,
+    head_cid: &multicid::Cid,
+    resolver: impl Resolver + Clone,
+) -> Result<(Log, VerificationReport), ResolveError> {
+    let rebuilt_plog = rebuild_plog(vlad, head_cid, resolver).await?;
+    let report = VerificationReport::new(&rebuilt_plog);
+    Ok((rebuilt_plog, report))
+}
+
+/// Fetches the entries from the head down to the foot and the first lock script,
+/// then rebuilds the [Log] without verifying it.
+async fn rebuild_plog(
+    vlad: &multicid::Vlad,
+    head_cid: &multicid::Cid,
+    resolver: impl Resolver + Clone,
+) -> Result<Log, ResolveError> {
     let fetched_entries = get_entry_chain(head_cid.clone(), resolver.clone()).await?;
 
     // Reconstruct the plog from the fetched entries
     let first_lock_cid = vlad.cid();
 
-    let entry_bytes = resolver
+    let first_lock_bytes = resolver
         .resolve(first_lock_cid)
         .await
         .map_err(|e| ResolveError::Other(Box::new(e)))?;
 
-    let maybe_first_lock_script = provenance_log::Script::try_from(entry_bytes.as_slice())
-        .map_err(|e| ResolveError::Other(Box::new(e)))?;
+    let maybe_first_lock_script = first_lock_from_bytes(first_lock_cid, &first_lock_bytes)?;
 
     // Get the last entry for the foot
     let last_entry = fetched_entries.last().ok_or(ResolveError::NoLastEntry)?;
@@ -243,38 +389,12 @@ pub async fn resolve_plog(
         .try_build()
         .map_err(|e| ResolveError::Other(Box::new(e)))?;
 
-    let plog_clone = rebuilt_plog.clone();
-
-    let verify_iter = &mut plog_clone.verify();
-
     // Check that first entry matches (using debug_assert for development checks)
     if let Some(head_entry) = fetched_entries.get(head_cid) {
         debug_assert_eq!(rebuilt_plog.entries[head_cid], head_entry.clone());
     }
 
-    // Collect individual verification counts
-    let mut verification_counts = Vec::new();
-
-    // the log should also verify
-    for ret in verify_iter {
-        match ret {
-            Ok((count, entry, kvp)) => {
-                verification_counts.push(count);
-                tracing::trace!("Verified entry: {:#?}", entry);
-                tracing::trace!("Verified count: {:#?}", count);
-                tracing::trace!("Verified kvp: {:#?}", kvp);
-            }
-            Err(e) => {
-                tracing::error!("Error: {:#?}", e);
-                return Err(ResolveError::VerificationError(e.to_string()));
-            }
-        }
-    }
-
-    Ok(ResolvedPlog {
-        log: rebuilt_plog,
-        verification_counts,
-    })
+    Ok(rebuilt_plog)
 }
 
 #[cfg(test)]
@@ -0,0 +1,230 @@
+//! Fork choice for when more than one head [Cid] is seen for the same [Vlad].
+//!
+//! Each candidate head is resolved and verified, then compared with the others.
+//! Candidates either share a prefix (one log is simply behind the other) or are a
+//! true fork, where two different entries have the same seqno. A [ForkChoicePolicy]
+//! decides which valid candidate wins, and the [ForkChoice] carries evidence about
+//! every losing candidate.
+
+use provenance_log::multicid;
+
+use multicid::{Cid, Vlad};
+use provenance_log::Log;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use super::{resolve_plog, ResolveError, ResolvedPlog, Resolver};
+
+/// How to pick a winner among valid candidate logs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ForkChoicePolicy {
+    /// The lowest verification cost wins, see [ResolvedPlog::compare]
+    #[default]
+    LowestCost,
+    /// The log with the most entries wins, ties are broken by lowest verification cost
+    LongestValid,
+    /// The first valid candidate in the given order wins
+    FirstSeen,
+}
+
+/// How one log relates to another log with the same [Vlad]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Relation {
+    /// Both logs have the same head
+    Same,
+    /// This log is a prefix of the other, it is behind
+    Behind,
+    /// The other log is a prefix of this one, it is ahead
+    Ahead,
+    /// The logs contain different entries with the same seqno
+    Fork {
+        /// The first seqno at which the logs differ
+        seqno: u64,
+        /// The last entry the logs have in common, if any
+        ancestor: Option<Cid>,
+        /// The [Cid] of the entry at `seqno` in this log
+        ours: Cid,
+        /// The [Cid] of the entry at `seqno` in the other log
+        theirs: Cid,
+    },
+}
+
+/// Works out how `ours` relates to `theirs` by comparing their entries seqno by seqno.
+pub fn relation(ours: &Log, theirs: &Log) -> Relation {
+    if ours.head == theirs.head {
+        return Relation::Same;
+    }
+
+    let ours_by_seqno = cids_by_seqno(ours);
+    let theirs_by_seqno = cids_by_seqno(theirs);
+
+    let mut ancestor = None;
+    for (seqno, our_cid) in &ours_by_seqno {
+        let Some(their_cid) = theirs_by_seqno.get(seqno) else {
+            break;
+        };
+        if our_cid != their_cid {
+            return Relation::Fork {
+                seqno: *seqno,
+                ancestor,
+                ours: our_cid.clone(),
+                theirs: their_cid.clone(),
+            };
+        }
+        ancestor = Some(our_cid.clone());
+    }
+
+    if ours_by_seqno.len() < theirs_by_seqno.len() {
+        Relation::Behind
+    } else {
+        Relation::Ahead
+    }
+}
+
+/// The entry [Cid]s of the [Log] in seqno order
+fn cids_by_seqno(log: &Log) -> BTreeMap<u64, Cid> {
+    log.entries
+        .iter()
+        .map(|(cid, entry)| (entry.seqno(), cid.clone()))
+        .collect()
+}
+
+/// Why a candidate head lost
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The candidate resolved and verified, but the policy preferred the winner
+    Valid {
+        /// How the candidate relates to the winner
+        relation: Relation,
+        /// The total verification count of the candidate
+        total_count: usize,
+        /// The number of entries in the candidate
+        entries_count: usize,
+        /// The resolved candidate, kept so a fork can be turned into an
+        /// [EquivocationProof](crate::equivocation::EquivocationProof)
+        resolved: Box<ResolvedPlog>,
+    },
+    /// The candidate failed to resolve or verify
+    Invalid(String),
+}
+
+/// Evidence about a losing candidate head
+#[derive(Debug, Clone)]
+pub struct Evidence {
+    /// The head [Cid] of the candidate
+    pub head: Cid,
+    /// What happened to the candidate
+    pub outcome: Outcome,
+}
+
+/// The result of choosing among several candidate heads
+#[derive(Debug, Clone)]
+pub struct ForkChoice {
+    /// The winning head [Cid]
+    pub head: Cid,
+    /// The winning resolved plog
+    pub winner: ResolvedPlog,
+    /// Evidence about every other candidate, in the order they were given
+    pub losers: Vec<Evidence>,
+}
+
+impl ForkChoice {
+    /// Returns true if any valid candidate is a true fork of the winner
+    pub fn has_fork(&self) -> bool {
+        self.losers.iter().any(|loser| {
+            matches!(
+                loser.outcome,
+                Outcome::Valid {
+                    relation: Relation::Fork { .. },
+                    ..
+                }
+            )
+        })
+    }
+}
+
+/// Resolves every candidate head for the [Vlad] and picks a winner with the [ForkChoicePolicy].
+///
+/// Duplicate heads are only resolved once. Fails with [ResolveError::NoValidHead]
+/// if none of the candidates resolve to a valid plog.
+pub async fn choose_head(
+    vlad: &Vlad,
+    heads: &[Cid],
+    resolver: impl Resolver + Clone,
+    policy: ForkChoicePolicy,
+) -> Result<ForkChoice, ResolveError> {
+    let mut candidates: Vec<(Cid, Result<ResolvedPlog, String>)> = Vec::new();
+    for head in heads {
+        if candidates.iter().any(|(h, _)| h == head) {
+            continue;
+        }
+        let resolved = resolve_plog(vlad, head, resolver.clone())
+            .await
+            .map_err(|e| e.to_string());
+        candidates.push((head.clone(), resolved));
+    }
+
+    choose(candidates, policy)
+}
+
+/// Picks a winner among already resolved candidates with the [ForkChoicePolicy].
+///
+/// Candidates that failed to resolve are passed along with their error message, so they
+/// show up in the evidence.
+pub fn choose(
+    mut candidates: Vec<(Cid, Result<ResolvedPlog, String>)>,
+    policy: ForkChoicePolicy,
+) -> Result<ForkChoice, ResolveError> {
+    let winner_idx = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, r))| r.as_ref().ok().map(|plog| (i, plog)))
+        .reduce(|best, next| match prefer(next.1, best.1, policy) {
+            Ordering::Less => next,
+            _ => best,
+        })
+        .map(|(i, _)| i)
+        .ok_or(ResolveError::NoValidHead)?;
+
+    let (head, winner) = candidates.remove(winner_idx);
+    let winner = winner.map_err(|_| ResolveError::NoValidHead)?;
+
+    let losers = candidates
+        .into_iter()
+        .map(|(head, resolved)| Evidence {
+            head,
+            outcome: match resolved {
+                Ok(plog) => Outcome::Valid {
+                    relation: relation(&plog.log, &winner.log),
+                    total_count: plog.total_count(),
+                    entries_count: plog.log.entries.len(),
+                    resolved: Box::new(plog),
+                },
+                Err(e) => Outcome::Invalid(e),
+            },
+        })
+        .collect();
+
+    Ok(ForkChoice {
+        head,
+        winner,
+        losers,
+    })
+}
+
+/// Orders `a` against `b`, [Ordering::Less] means `a` is preferred.
+///
+/// Candidates arrive in the order they were seen, so [Ordering::Equal] keeps the earlier one.
+fn prefer(a: &ResolvedPlog, b: &ResolvedPlog, policy: ForkChoicePolicy) -> Ordering {
+    match policy {
+        ForkChoicePolicy::LowestCost => a.compare(b),
+        ForkChoicePolicy::LongestValid => b
+            .log
+            .entries
+            .len()
+            .cmp(&a.log.entries.len())
+            .then_with(|| a.total_count().cmp(&b.total_count())),
+        ForkChoicePolicy::FirstSeen => Ordering::Equal,
+    }
+}
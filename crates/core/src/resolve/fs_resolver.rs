@@ -0,0 +1,148 @@
+//! A directory backed block store, in the spirit of a minimal flatfs.
+//!
+//! Each block is stored as a single file in the root directory, named by the
+//! multibase encoding of its [Cid](multicid::Cid). Blocks are verified against their
+//! [Cid](multicid::Cid) both when they are stored and when they are read back.
+
+use provenance_log::{multibase, multicid, Log};
+
+use multibase::Base;
+use multicid::EncodedCid;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{future::Future, pin::Pin};
+
+use super::{plog_blocks, verify_block, Publisher, ResolveError, Resolver};
+
+/// The multibase used for the block file names.
+pub const FILE_NAME_BASE: Base = Base::Base32Lower;
+
+/// A [Resolver] and [Publisher] that keeps blocks as files in a directory.
+#[derive(Clone, Debug)]
+pub struct FsBlockstore {
+    root: PathBuf,
+}
+
+impl FsBlockstore {
+    /// Opens the block store at the given directory, creating it if it does not exist.
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, FsBlockstoreError> {
+        let root = root.as_ref().to_path_buf();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// The root directory of the block store
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of the file the block for the given [Cid](multicid::Cid) is stored in.
+    pub fn block_path(&self, cid: &multicid::Cid) -> PathBuf {
+        self.root
+            .join(EncodedCid::new(FILE_NAME_BASE, cid.clone()).to_string())
+    }
+
+    /// Returns true if a block is stored for the given [Cid](multicid::Cid).
+    pub fn has(&self, cid: &multicid::Cid) -> bool {
+        self.block_path(cid).is_file()
+    }
+
+    /// Reads the block for the given [Cid](multicid::Cid), verifying its content hash.
+    pub fn get(&self, cid: &multicid::Cid) -> Result<Vec<u8>, FsBlockstoreError> {
+        let path = self.block_path(cid);
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(FsBlockstoreError::BlockNotFound(path))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        verify_block(cid, &data)?;
+        Ok(data)
+    }
+
+    /// Verifies and stores the block under the given [Cid](multicid::Cid).
+    ///
+    /// The block is written to a temporary file first and then renamed into place,
+    /// so readers never observe a partially written block.
+    pub fn put(&self, cid: &multicid::Cid, data: &[u8]) -> Result<(), FsBlockstoreError> {
+        verify_block(cid, data)?;
+
+        let path = self.block_path(cid);
+        if path.is_file() {
+            return Ok(());
+        }
+
+        let tmp = temp_path(&path);
+        std::fs::write(&tmp, data)?;
+        if let Err(e) = std::fs::rename(&tmp, &path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Removes the block for the given [Cid](multicid::Cid), if any.
+    pub fn remove(&self, cid: &multicid::Cid) -> Result<(), FsBlockstoreError> {
+        match std::fs::remove_file(self.block_path(cid)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Stores every block of the [Log], the blocking equivalent of [publish_plog](super::publish_plog).
+    pub fn put_log(&self, log: &Log) -> Result<(), FsBlockstoreError> {
+        for (cid, data) in plog_blocks(log) {
+            self.put(&cid, &data)?;
+        }
+        Ok(())
+    }
+}
+
+/// A temporary file name next to the file, unique to this process and call,
+/// so concurrent writers of the same file never share a temporary file.
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.{n}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+impl Resolver for FsBlockstore {
+    type Error = FsBlockstoreError;
+
+    fn resolve(
+        &self,
+        cid: &multicid::Cid,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::Error>> + Send>> {
+        let store = self.clone();
+        let cid = cid.clone();
+        Box::pin(async move { store.get(&cid) })
+    }
+}
+
+impl Publisher for FsBlockstore {
+    type Error = FsBlockstoreError;
+
+    fn publish(
+        &self,
+        cid: &multicid::Cid,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send>> {
+        let store = self.clone();
+        let cid = cid.clone();
+        Box::pin(async move { store.put(&cid, &data) })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FsBlockstoreError {
+    #[error("Block not found: {0}")]
+    BlockNotFound(PathBuf),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid block: {0}")]
+    InvalidBlock(#[from] ResolveError),
+}
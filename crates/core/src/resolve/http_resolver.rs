@@ -0,0 +1,190 @@
+//! A resolver that fetches raw blocks over HTTP, following the IPFS
+//! [trustless gateway](https://specs.ipfs.tech/http-gateways/trustless-gateway/) convention:
+//! `GET {gateway}/ipfs/{cid}?format=raw`.
+//!
+//! Gateways are untrusted, so every body is verified against the requested
+//! [Cid](multicid::Cid) before it is returned.
+
+use provenance_log::{multibase, multicid};
+
+use multibase::Base;
+use multicid::EncodedCid;
+use std::time::Duration;
+use std::{future::Future, pin::Pin};
+
+use super::{verify_block, Resolver};
+
+/// The default request timeout
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default maximum block size, 2MiB, which is the largest block most gateways will serve
+pub const DEFAULT_MAX_BLOCK_SIZE: usize = 2 * 1024 * 1024;
+
+/// The media type of a raw block response
+const RAW_BLOCK_MEDIA_TYPE: &str = "application/vnd.ipld.raw";
+
+/// A [Resolver] that fetches blocks from one or more trustless HTTP gateways.
+///
+/// Gateways are tried in order until one returns a block that matches the requested [Cid](multicid::Cid).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use bestsign_core::http_resolver::HttpResolver;
+///
+/// let resolver = HttpResolver::new(["https://trustless-gateway.link"])
+///     .with_timeout(Duration::from_secs(10))
+///     .with_max_block_size(1024 * 1024);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpResolver {
+    client: reqwest::Client,
+    gateways: Vec<String>,
+    timeout: Duration,
+    max_block_size: usize,
+}
+
+impl HttpResolver {
+    /// Create a new resolver for the given gateway base URLs, such as `https://ipfs.io`
+    pub fn new<S: AsRef<str>>(gateways: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            gateways: gateways
+                .into_iter()
+                .map(|g| g.as_ref().trim_end_matches('/').to_string())
+                .collect(),
+            timeout: DEFAULT_TIMEOUT,
+            max_block_size: DEFAULT_MAX_BLOCK_SIZE,
+        }
+    }
+
+    /// Set the timeout for each request
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum block size, larger responses are rejected
+    pub fn with_max_block_size(mut self, max_block_size: usize) -> Self {
+        self.max_block_size = max_block_size;
+        self
+    }
+
+    /// Use the given [reqwest::Client], for example to share a connection pool
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// The gateway base URLs, in the order they are tried
+    pub fn gateways(&self) -> &[String] {
+        &self.gateways
+    }
+
+    /// The raw block URL for the [Cid](multicid::Cid) on the given gateway
+    pub fn block_url(gateway: &str, cid: &multicid::Cid) -> String {
+        let encoded = EncodedCid::new(Base::Base32Lower, cid.clone());
+        format!("{gateway}/ipfs/{encoded}?format=raw")
+    }
+
+    /// Fetches and verifies the block from a single gateway
+    async fn fetch(
+        client: reqwest::Client,
+        url: String,
+        cid: multicid::Cid,
+        timeout: Duration,
+        max_block_size: usize,
+    ) -> Result<Vec<u8>, HttpResolverError> {
+        let mut response = client
+            .get(&url)
+            .header(reqwest::header::ACCEPT, RAW_BLOCK_MEDIA_TYPE)
+            .timeout(timeout)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(HttpResolverError::Status {
+                url,
+                status: response.status().as_u16(),
+            });
+        }
+
+        // refuse early if the gateway tells us the block is too large
+        if let Some(len) = response.content_length() {
+            if len > max_block_size as u64 {
+                return Err(HttpResolverError::TooLarge {
+                    url,
+                    limit: max_block_size,
+                });
+            }
+        }
+
+        // the length header is optional and not to be trusted, so also count as we read
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if data.len() + chunk.len() > max_block_size {
+                return Err(HttpResolverError::TooLarge {
+                    url,
+                    limit: max_block_size,
+                });
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        verify_block(&cid, &data).map_err(|e| HttpResolverError::InvalidBlock {
+            url,
+            reason: e.to_string(),
+        })?;
+
+        Ok(data)
+    }
+}
+
+impl Resolver for HttpResolver {
+    type Error = HttpResolverError;
+
+    fn resolve(
+        &self,
+        cid: &multicid::Cid,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::Error>> + Send>> {
+        let resolver = self.clone();
+        let cid = cid.clone();
+        Box::pin(async move {
+            let mut last_err = HttpResolverError::NoGateways;
+            for gateway in &resolver.gateways {
+                let url = Self::block_url(gateway, &cid);
+                match Self::fetch(
+                    resolver.client.clone(),
+                    url,
+                    cid.clone(),
+                    resolver.timeout,
+                    resolver.max_block_size,
+                )
+                .await
+                {
+                    Ok(data) => return Ok(data),
+                    Err(e) => {
+                        tracing::debug!("Gateway {} failed: {}", gateway, e);
+                        last_err = e;
+                    }
+                }
+            }
+            Err(last_err)
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HttpResolverError {
+    #[error("No gateways configured")]
+    NoGateways,
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Gateway returned status {status} for {url}")]
+    Status { url: String, status: u16 },
+    #[error("Block from {url} exceeds the limit of {limit} bytes")]
+    TooLarge { url: String, limit: usize },
+    #[error("Invalid block from {url}: {reason}")]
+    InvalidBlock { url: String, reason: String },
+}
@@ -0,0 +1,188 @@
+//! Naming maps a [Vlad] to the [Cid] of the latest head [Entry](provenance_log::Entry)
+//! of its plog.
+//!
+//! On the network this is usually a DHT record (`vlad -> head`), but any
+//! mutable pointer will do, so the lookup is defined by the [HeadResolver] trait.
+//! Once the head is known, [resolve_by_vlad] fetches and verifies the plog.
+
+use provenance_log::{multibase, multicid};
+
+use multibase::Base;
+use multicid::{Cid, EncodedVlad, Vlad};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::{future::Future, pin::Pin};
+
+use super::{resolve_plog, ResolveError, ResolvedPlog, Resolver};
+
+/// A trait for resolving a [Vlad] to its current head [Cid].
+#[allow(clippy::type_complexity)]
+pub trait HeadResolver {
+    type Error: std::error::Error + 'static;
+
+    fn resolve_head(
+        &self,
+        vlad: &Vlad,
+    ) -> Pin<Box<dyn Future<Output = Result<Cid, Self::Error>> + Send>>;
+}
+
+/// Look up the head [Cid] for the [Vlad] with the [HeadResolver], then resolve and
+/// verify the plog with [resolve_plog].
+pub async fn resolve_by_vlad(
+    vlad: &Vlad,
+    heads: &impl HeadResolver,
+    resolver: impl Resolver + Clone,
+) -> Result<ResolvedPlog, ResolveError> {
+    let head = heads
+        .resolve_head(vlad)
+        .await
+        .map_err(|e| ResolveError::Other(Box::new(e)))?;
+
+    resolve_plog(vlad, &head, resolver).await
+}
+
+/// The multibase used to name [Vlad]s in head stores, the same one used for display.
+const VLAD_BASE: Base = Base::Base36Lower;
+
+/// An in-memory [HeadResolver], handy for tests and as a local cache.
+///
+/// Clones share the same records.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryHeadStore {
+    heads: Arc<Mutex<HashMap<Vec<u8>, Cid>>>,
+}
+
+impl MemoryHeadStore {
+    /// Create a new, empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the head [Cid] for the [Vlad], returning the previous head, if any
+    pub fn set_head(&self, vlad: &Vlad, head: &Cid) -> Option<Cid> {
+        let key: Vec<u8> = vlad.clone().into();
+        self.heads
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, head.clone())
+    }
+
+    /// Get the head [Cid] for the [Vlad]
+    pub fn get_head(&self, vlad: &Vlad) -> Option<Cid> {
+        let key: Vec<u8> = vlad.clone().into();
+        self.heads
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+            .cloned()
+    }
+
+    /// Remove the record for the [Vlad], returning the head, if any
+    pub fn remove(&self, vlad: &Vlad) -> Option<Cid> {
+        let key: Vec<u8> = vlad.clone().into();
+        self.heads
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&key)
+    }
+}
+
+impl HeadResolver for MemoryHeadStore {
+    type Error = HeadStoreError;
+
+    fn resolve_head(
+        &self,
+        vlad: &Vlad,
+    ) -> Pin<Box<dyn Future<Output = Result<Cid, Self::Error>> + Send>> {
+        let head = self.get_head(vlad).ok_or_else(|| {
+            HeadStoreError::NotFound(EncodedVlad::new(VLAD_BASE, vlad.clone()).to_string())
+        });
+        Box::pin(async move { head })
+    }
+}
+
+/// A [HeadResolver] that keeps one file per [Vlad] in a directory.
+///
+/// Files are named by the multibase encoded [Vlad] and contain the head [Cid] bytes.
+#[cfg(feature = "fs")]
+#[derive(Clone, Debug)]
+pub struct FsHeadStore {
+    root: std::path::PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl FsHeadStore {
+    /// Opens the store at the given directory, creating it if it does not exist.
+    pub fn open(root: impl AsRef<std::path::Path>) -> Result<Self, HeadStoreError> {
+        let root = root.as_ref().to_path_buf();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// The path of the file holding the head of the [Vlad]
+    pub fn head_path(&self, vlad: &Vlad) -> std::path::PathBuf {
+        self.root
+            .join(EncodedVlad::new(VLAD_BASE, vlad.clone()).to_string())
+    }
+
+    /// Set the head [Cid] for the [Vlad], replacing the file atomically
+    pub fn set_head(&self, vlad: &Vlad, head: &Cid) -> Result<(), HeadStoreError> {
+        let path = self.head_path(vlad);
+        let tmp = super::fs_resolver::temp_path(&path);
+        let head_bytes: Vec<u8> = head.clone().into();
+        std::fs::write(&tmp, head_bytes)?;
+        if let Err(e) = std::fs::rename(&tmp, &path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Get the head [Cid] for the [Vlad]
+    pub fn get_head(&self, vlad: &Vlad) -> Result<Cid, HeadStoreError> {
+        let path = self.head_path(vlad);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(HeadStoreError::NotFound(
+                    EncodedVlad::new(VLAD_BASE, vlad.clone()).to_string(),
+                ))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Cid::try_from(bytes.as_slice())?)
+    }
+
+    /// Remove the record for the [Vlad]
+    pub fn remove(&self, vlad: &Vlad) -> Result<(), HeadStoreError> {
+        match std::fs::remove_file(self.head_path(vlad)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl HeadResolver for FsHeadStore {
+    type Error = HeadStoreError;
+
+    fn resolve_head(
+        &self,
+        vlad: &Vlad,
+    ) -> Pin<Box<dyn Future<Output = Result<Cid, Self::Error>> + Send>> {
+        let store = self.clone();
+        let vlad = vlad.clone();
+        Box::pin(async move { store.get_head(&vlad) })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HeadStoreError {
+    #[error("No head found for Vlad {0}")]
+    NotFound(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid head Cid: {0}")]
+    InvalidCid(#[from] multicid::Error),
+}
@@ -1,12 +1,16 @@
-use provenance_log::{multibase, multicid, multicodec, multihash, multikey, multiutil};
+use provenance_log::{
+    multibase, multicid, multicodec, multihash, multikey, multisig, multitrait, multiutil,
+};
 
 use multibase::Base;
 use multicid::{Cid, EncodedCid, EncodedVlad, Vlad};
 use multicodec::Codec;
-use multihash::EncodedMultihash;
-use multikey::{Multikey, Views as _};
+use multihash::{EncodedMultihash, Multihash};
+use multikey::{EncodedMultikey, Multikey, Views as _};
+use multisig::{EncodedMultisig, Multisig};
+use multitrait::Null;
 use multiutil::{BaseEncoded, CodecInfo, DetectedEncoder, EncodingInfo};
-use provenance_log::{entry, vm, Key, Log, LogValue, OpId, Pairs, Script};
+use provenance_log::{entry, vm, Entry, Key, Log, LogValue, Op, OpId, Pairs, Script};
 
 use crate::{
     error::{OpenError, PlogError},
@@ -75,12 +79,37 @@ where
     }
 }
 
+/// Options for how [DisplayData] encodes values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayOptions {
+    /// The multibase for Vlads, Cids, fingerprints and keys
+    pub base: Base,
+    /// The hash codec for key fingerprints
+    pub fingerprint_codec: Codec,
+    /// Whether to include raw bytes of Vlads and data values
+    pub include_bytes: bool,
+    /// Whether to include the encoded public key of Multikeys
+    pub include_key_material: bool,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            base: Base::Base36Lower,
+            fingerprint_codec: Codec::Blake3,
+            include_bytes: true,
+            include_key_material: false,
+        }
+    }
+}
+
 /// Vlad details
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VladDetails {
     pub value: Vlad,
-    pub bytes: Vec<u8>,
+    /// The raw bytes, if [DisplayOptions::include_bytes] is set
+    pub bytes: Option<Vec<u8>>,
     pub encoded: String,
     pub verified: bool,
 }
@@ -99,15 +128,40 @@ pub enum DisplayData {
         codec_type: &'static str,
         codec: String,
         fingerprint: String,
+        /// The encoded public key, if [DisplayOptions::include_key_material] is set
+        public_key: Option<String>,
+        /// The threshold of a threshold key
+        threshold: Option<usize>,
+        /// The limit of a threshold key
+        limit: Option<usize>,
+    },
+    Multihash {
+        key_path: Key,
+        codec_type: &'static str,
+        /// The hash codec
+        codec: String,
+        encoded: String,
+    },
+    Multisig {
+        key_path: Key,
+        codec_type: &'static str,
+        /// The signature codec
+        codec: String,
+        encoded: String,
     },
     Vlad {
         codec_type: &'static str,
         encoded: String,
-        bytes: Vec<u8>,
+        /// The raw bytes, if [DisplayOptions::include_bytes] is set
+        bytes: Option<Vec<u8>>,
     },
     Script {
         key_path: Key,
         codec_type: &'static str,
+        /// The key-path the script itself is bound to
+        script_path: Key,
+        /// The source of a code script
+        source: Option<String>,
         length: usize,
     },
     Cid {
@@ -118,7 +172,8 @@ pub enum DisplayData {
     },
     Data {
         key_path: Key,
-        value: Vec<u8>,
+        /// The raw bytes, if [DisplayOptions::include_bytes] is set
+        value: Option<Vec<u8>>,
     },
     Str {
         key_path: Key,
@@ -127,10 +182,24 @@ pub enum DisplayData {
     Nil {
         key_path: Key,
     },
+    /// A value with a codec that has no display of its own
+    Unknown {
+        key_path: Key,
+        codec: String,
+        /// The raw bytes, if [DisplayOptions::include_bytes] is set
+        bytes: Option<Vec<u8>>,
+    },
 }
 
 /// A utility method to extract Key Value pairs from a [provenance_log::Log]
 pub fn get_display_data(log: &Log) -> Result<DisplayData, Error> {
+    get_display_data_with(log, &DisplayOptions::default())
+}
+
+/// Like [get_display_data], encoding values with the [DisplayOptions]
+///
+/// Fails if any entry of the [Log] fails to verify.
+pub fn get_display_data_with(log: &Log, options: &DisplayOptions) -> Result<DisplayData, Error> {
     let mut vi = log.verify();
     let (_, _, mut kvp) = vi.next().ok_or::<Error>(PlogError::NoFirstEntry.into())??;
 
@@ -141,14 +210,13 @@ pub fn get_display_data(log: &Log) -> Result<DisplayData, Error> {
         // or InvalidVMValue
         .ok_or::<Error>(PlogError::InvalidVMValue.into())?;
 
+    // a log that fails to verify has no state worth showing
     for ret in vi {
-        match ret {
-            Ok((_, _, ref pairs)) => kvp = pairs.clone(),
-            Err(e) => tracing::debug!("verify failed: {}", e.to_string()),
-        }
+        let (_, _, pairs) = ret?;
+        kvp = pairs;
     }
 
-    let vlad_encoded = EncodedVlad::new(Base::Base36Lower, log.vlad.clone()).to_string();
+    let vlad_encoded = EncodedVlad::new(options.base, log.vlad.clone()).to_string();
     let vlad_verified = log.vlad.verify(&vlad_key).is_ok();
 
     //let fingerprint = vlad_key.fingerprint_view()?.fingerprint(Codec::Blake3)?;
@@ -156,80 +224,13 @@ pub fn get_display_data(log: &Log) -> Result<DisplayData, Error> {
 
     let kvp_data = kvp
         .iter()
-        .map(|(k, val)| -> Result<DisplayData, Error> {
-            let value = if let Some(codec) = get_codec_from_plog_value(val) {
-                let v = kvp
-                    .get(k.as_str())
-                    .ok_or::<Error>(PlogError::NoKeyPath.into())?;
-                match codec {
-                    Codec::Multikey => {
-                        let key: Multikey =
-                            try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
-                        let fingerprint = key.fingerprint_view()?.fingerprint(Codec::Blake3)?;
-                        let ef = EncodedMultihash::new(Base::Base36Lower, fingerprint).to_string();
-                        DisplayData::Multikey {
-                            key_path: k.clone(),
-                            codec_type: codec.into(),
-                            codec: key.codec().to_string(),
-                            fingerprint: ef,
-                        }
-                    }
-                    Codec::Vlad => {
-                        let vlad: Vlad =
-                            try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
-                        let bytes: Vec<u8> = vlad.clone().into();
-                        DisplayData::Vlad {
-                            codec_type: codec.into(),
-                            encoded: EncodedVlad::new(Base::Base36Lower, vlad).to_string(),
-                            bytes,
-                        }
-                    }
-                    Codec::ProvenanceLogScript => {
-                        let script: Script =
-                            try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
-                        DisplayData::Script {
-                            key_path: k.clone(),
-                            codec_type: codec.into(),
-                            length: script.as_ref().len(),
-                        }
-                    }
-                    Codec::Cidv1 | Codec::Cidv2 | Codec::Cidv3 => {
-                        let cid: Cid =
-                            try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
-                        DisplayData::Cid {
-                            key_path: k.clone(),
-                            codec: cid.codec().to_string(),
-                            encoded: EncodedCid::new(Base::Base36Lower, cid).to_string(),
-                            codec_type: codec.into(),
-                        }
-                    }
-                    _ => DisplayData::Nil {
-                        key_path: k.clone(),
-                    },
-                }
-            } else {
-                match val {
-                    LogValue::Data(v) => DisplayData::Data {
-                        key_path: k.clone(),
-                        value: v.to_vec(),
-                    },
-                    LogValue::Str(s) => DisplayData::Str {
-                        key_path: k.clone(),
-                        value: s.to_string(),
-                    },
-                    _ => DisplayData::Nil {
-                        key_path: k.clone(),
-                    },
-                }
-            };
-            Ok(value)
-        })
+        .map(|(k, val)| display_value_with(k, val, options))
         .collect::<Result<Vec<DisplayData>, Error>>()?;
 
     let display_data = DisplayData::ReturnValue {
         vlad: VladDetails {
             value: log.vlad.clone(),
-            bytes: log.vlad.clone().into(),
+            bytes: options.include_bytes.then(|| log.vlad.clone().into()),
             encoded: vlad_encoded,
             verified: vlad_verified,
         },
@@ -240,6 +241,260 @@ pub fn get_display_data(log: &Log) -> Result<DisplayData, Error> {
     Ok(display_data)
 }
 
+/// Turns one key-value pair into [DisplayData], decoding typed values by their codec
+pub fn display_value(k: &Key, val: &LogValue) -> Result<DisplayData, Error> {
+    display_value_with(k, val, &DisplayOptions::default())
+}
+
+/// Like [display_value], encoding values with the [DisplayOptions]
+pub fn display_value_with(
+    k: &Key,
+    val: &LogValue,
+    options: &DisplayOptions,
+) -> Result<DisplayData, Error> {
+    let Some(codec) = get_codec_from_plog_value(val) else {
+        return Ok(match val {
+            LogValue::Data(v) => DisplayData::Data {
+                key_path: k.clone(),
+                value: options.include_bytes.then(|| v.to_vec()),
+            },
+            LogValue::Str(s) => DisplayData::Str {
+                key_path: k.clone(),
+                value: s.to_string(),
+            },
+            _ => DisplayData::Nil {
+                key_path: k.clone(),
+            },
+        });
+    };
+    let v = vm_value(k, val).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
+
+    let value = match codec {
+        Codec::Multikey => {
+            let key: Multikey = try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
+            let fingerprint = key
+                .fingerprint_view()?
+                .fingerprint(options.fingerprint_codec)?;
+            let ef = EncodedMultihash::new(options.base, fingerprint).to_string();
+            // only ever show the public half of a key
+            let public_key = options
+                .include_key_material
+                .then(|| key.conv_view().and_then(|v| v.to_public_key()).ok())
+                .flatten()
+                .map(|pk| EncodedMultikey::new(options.base, pk).to_string());
+            let threshold_attr = key.threshold_attr_view().ok();
+            DisplayData::Multikey {
+                key_path: k.clone(),
+                codec_type: codec.into(),
+                codec: key.codec().to_string(),
+                fingerprint: ef,
+                public_key,
+                threshold: threshold_attr.as_ref().and_then(|v| v.threshold().ok()),
+                limit: threshold_attr.as_ref().and_then(|v| v.limit().ok()),
+            }
+        }
+        Codec::Vlad => {
+            let vlad: Vlad = try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
+            let bytes: Vec<u8> = vlad.clone().into();
+            DisplayData::Vlad {
+                codec_type: codec.into(),
+                encoded: EncodedVlad::new(options.base, vlad).to_string(),
+                bytes: options.include_bytes.then_some(bytes),
+            }
+        }
+        Codec::ProvenanceLogScript => {
+            let script: Script =
+                try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
+            display_script(k, &script)
+        }
+        Codec::Cidv1 | Codec::Cidv2 | Codec::Cidv3 => {
+            let cid: Cid = try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
+            DisplayData::Cid {
+                key_path: k.clone(),
+                codec: cid.codec().to_string(),
+                encoded: EncodedCid::new(options.base, cid).to_string(),
+                codec_type: codec.into(),
+            }
+        }
+        Codec::Multihash => {
+            let mh: Multihash = try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
+            DisplayData::Multihash {
+                key_path: k.clone(),
+                codec_type: codec.into(),
+                codec: mh.codec().to_string(),
+                encoded: EncodedMultihash::new(options.base, mh).to_string(),
+            }
+        }
+        Codec::Multisig => {
+            let ms: Multisig = try_extract(&v).ok_or::<Error>(PlogError::InvalidVMValue.into())?;
+            DisplayData::Multisig {
+                key_path: k.clone(),
+                codec_type: codec.into(),
+                codec: ms.codec().to_string(),
+                encoded: EncodedMultisig::new(options.base, ms).to_string(),
+            }
+        }
+        _ => DisplayData::Unknown {
+            key_path: k.clone(),
+            codec: codec.to_string(),
+            bytes: options.include_bytes.then(|| match val {
+                LogValue::Data(v) => v.clone(),
+                LogValue::Str(s) => s.as_bytes().to_vec(),
+                _ => Vec::new(),
+            }),
+        },
+    };
+    Ok(value)
+}
+
+/// The [DisplayData] of a [Script] stored at the key-path
+fn display_script(key_path: &Key, script: &Script) -> DisplayData {
+    DisplayData::Script {
+        key_path: key_path.clone(),
+        codec_type: Codec::ProvenanceLogScript.into(),
+        script_path: script.path(),
+        source: match script {
+            Script::Code(_, code) => Some(code.clone()),
+            _ => None,
+        },
+        length: script.as_ref().len(),
+    }
+}
+
+/// The kind of an op in an [Entry]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum OpKind {
+    /// The op does nothing to the key-path
+    Noop,
+    /// The op deletes the key-path
+    Delete,
+    /// The op sets the key-path to a value
+    Update,
+}
+
+/// Display record of one op in an [Entry]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpDisplay {
+    /// The kind of op
+    pub kind: OpKind,
+    /// The key-path the op acts on
+    pub key_path: Key,
+    /// The typed value of an update
+    pub value: Option<DisplayData>,
+}
+
+/// Display record of one [Entry] in a [Log]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryDisplay {
+    /// The seqno of the entry
+    pub seqno: u64,
+    /// The encoded [Cid] of the entry
+    pub cid: String,
+    /// The encoded [Cid] of the previous entry, none for the first entry
+    pub prev: Option<String>,
+    /// The encoded [Cid] of the lipmaa long-hop entry, if set
+    pub lipmaa: Option<String>,
+    /// The ops of the entry, in order
+    pub ops: Vec<OpDisplay>,
+    /// The key-paths of the lock scripts the entry sets for the next entry
+    pub locks: Vec<Key>,
+    /// The unlock script of the entry
+    pub unlock: DisplayData,
+    /// The codec of the entry proof, if it decodes
+    pub proof_codec: Option<String>,
+}
+
+impl EntryDisplay {
+    /// Builds the display record for the [Entry]
+    pub fn new(entry: &Entry) -> Result<Self, Error> {
+        Self::new_with(entry, &DisplayOptions::default())
+    }
+
+    /// Like [new](Self::new), encoding values with the [DisplayOptions]
+    pub fn new_with(entry: &Entry, options: &DisplayOptions) -> Result<Self, Error> {
+        let encode = |cid: Cid| -> Option<String> {
+            (cid != Cid::null()).then(|| EncodedCid::new(options.base, cid).to_string())
+        };
+
+        let ops = entry
+            .ops()
+            .map(|op| -> Result<OpDisplay, Error> {
+                Ok(match op {
+                    Op::Noop(k) => OpDisplay {
+                        kind: OpKind::Noop,
+                        key_path: k.clone(),
+                        value: None,
+                    },
+                    Op::Delete(k) => OpDisplay {
+                        kind: OpKind::Delete,
+                        key_path: k.clone(),
+                        value: None,
+                    },
+                    Op::Update(k, v) => OpDisplay {
+                        kind: OpKind::Update,
+                        key_path: k.clone(),
+                        value: Some(display_value_with(k, v, options)?),
+                    },
+                })
+            })
+            .collect::<Result<Vec<OpDisplay>, Error>>()?;
+
+        let unlock = entry.unlock();
+
+        Ok(Self {
+            seqno: entry.seqno(),
+            cid: EncodedCid::new(options.base, entry.cid()).to_string(),
+            prev: encode(entry.prev()),
+            lipmaa: encode(entry.lipmaa()),
+            ops,
+            locks: entry.locks().map(|lock| lock.path()).collect(),
+            unlock: display_script(&unlock.path(), unlock),
+            proof_codec: entry_proof(entry)
+                .ok()
+                .map(|(_, proof)| proof.codec().to_string()),
+        })
+    }
+}
+
+/// The display records of every [Entry] in the [Log], from foot to head.
+///
+/// The entries are not verified, see [VerificationReport](crate::VerificationReport) for that.
+pub fn get_timeline(log: &Log) -> Result<Vec<EntryDisplay>, Error> {
+    get_timeline_with(log, &DisplayOptions::default())
+}
+
+/// Like [get_timeline], encoding values with the [DisplayOptions]
+pub fn get_timeline_with(log: &Log, options: &DisplayOptions) -> Result<Vec<EntryDisplay>, Error> {
+    let mut entries: Vec<&Entry> = log.entries.values().collect();
+    entries.sort_by_key(|entry| entry.seqno());
+    entries
+        .into_iter()
+        .map(|entry| EntryDisplay::new_with(entry, options))
+        .collect()
+}
+
+/// The [vm::Value] of a [LogValue], as the verified key-value pairs return it
+fn vm_value(key_path: &Key, value: &LogValue) -> Option<vm::Value> {
+    match value {
+        LogValue::Data(data) => Some(vm::Value::Bin {
+            hint: key_path.to_string(),
+            data: data.clone(),
+        }),
+        LogValue::Str(s) => Some(vm::Value::Str {
+            hint: key_path.to_string(),
+            data: s.clone(),
+        }),
+        _ => None,
+    }
+}
+
 /// Utility method to extract a [Codec] from a [provenance_log::LogValue]
 fn get_codec_from_plog_value(value: &LogValue) -> Option<Codec> {
     match value {
@@ -249,6 +504,71 @@ fn get_codec_from_plog_value(value: &LogValue) -> Option<Codec> {
     }
 }
 
+/// Returns the bytes the [Entry] proof was generated over, which is the [Entry]
+/// with an empty proof, along with the proof as a [Multisig].
+pub fn entry_proof(entry: &Entry) -> Result<(Vec<u8>, Multisig), Error> {
+    let Some(vm::Value::Bin { data, .. }) = entry.get("/entry/") else {
+        return Err(PlogError::InvalidVMValue.into());
+    };
+    let Some(vm::Value::Bin { data: proof, .. }) = entry.get("/entry/proof") else {
+        return Err(PlogError::InvalidVMValue.into());
+    };
+    Ok((data, Multisig::try_from(proof.as_slice())?))
+}
+
+/// Returns true if the [Entry] proof is a valid signature by the given public [Multikey]
+pub fn verify_entry_proof(entry: &Entry, key: &Multikey) -> bool {
+    let Ok((data, proof)) = entry_proof(entry) else {
+        return false;
+    };
+    key.verify_view()
+        .and_then(|v| v.verify(&proof, Some(&data)))
+        .is_ok()
+}
+
+/// Returns all of the [Multikey]s among the given key-value pairs, with their key-paths
+///
+/// Pass the `kvp.iter()` of a verified state.
+pub fn multikeys<'a>(
+    pairs: impl IntoIterator<Item = (&'a Key, &'a LogValue)>,
+) -> Vec<(Key, Multikey)> {
+    pairs
+        .into_iter()
+        .filter_map(|(k, v)| match v {
+            LogValue::Data(data) => Multikey::try_from(data.as_slice())
+                .ok()
+                .map(|mk| (k.clone(), mk)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The secret key codec matching a public key codec, other codecs are returned as is
+pub fn secret_codec(codec: Codec) -> Codec {
+    match codec {
+        Codec::Ed25519Pub => Codec::Ed25519Priv,
+        Codec::Secp256K1Pub => Codec::Secp256K1Priv,
+        Codec::P256Pub => Codec::P256Priv,
+        Codec::Bls12381G1Pub => Codec::Bls12381G1Priv,
+        Codec::Bls12381G2Pub => Codec::Bls12381G2Priv,
+        codec => codec,
+    }
+}
+
+/// The secret key codec of the [Multikey] stored at the key-path in the verified
+/// state of the [Log], such as the codec to request the `/pubkey` signing key with
+///
+/// Returns None if there is no [Multikey] at the key-path.
+pub fn key_codec(log: &Log, key_path: &str) -> Result<Option<Codec>, Error> {
+    let checkpoint = crate::Checkpoint::from_log(log)?;
+    Ok(match checkpoint.get(key_path) {
+        Some(LogValue::Data(data)) => Multikey::try_from(data.as_slice())
+            .ok()
+            .map(|mk| secret_codec(mk.codec())),
+        _ => None,
+    })
+}
+
 /// Utility for converting a str slice to Vlad bytes
 pub fn decode_vlad(s: &str) -> Result<Vec<u8>, Error> {
     let encoded_vlad = EncodedVlad::try_from(s)?;
@@ -0,0 +1,137 @@
+//! Tests for resuming verification and updates from a checkpoint.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    error::CheckpointError,
+    ops::{
+        update::{OpParams, UpdateConfig},
+        update_plog_from,
+    },
+    provenance_log::{Key, LogValue},
+    Checkpoint, Error,
+};
+use fixtures::{append_str, generate_updated_plog, init_logger, unlock_script, TestKeyManager};
+
+fn hello_config(key_manager: &TestKeyManager, value: &str) -> UpdateConfig {
+    UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap_or_default())
+        .add_op(OpParams::UseStr {
+            key: Key::try_from("/hello/").unwrap(),
+            s: value.to_string(),
+        })
+        .build()
+}
+
+#[test]
+fn test_update_from_checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (mut plog, mut key_manager) = generate_updated_plog(2)?;
+    append_str(&mut plog, &mut key_manager, "/gone", "soon")?;
+
+    let mut checkpoint = Checkpoint::from_log(&plog)?;
+    assert_eq!(checkpoint.cid, plog.head);
+    assert_eq!(checkpoint.seqno, 3);
+    assert_eq!(
+        checkpoint.get("/gone"),
+        Some(&LogValue::Str("soon".to_string()))
+    );
+
+    // deleting a key drops it from the snapshot
+    let config = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap_or_default())
+        .add_op(OpParams::Delete {
+            key: Key::try_from("/gone")?,
+        })
+        .build();
+    checkpoint = update_plog_from(&mut plog, &checkpoint, &config, &mut key_manager)?;
+    assert_eq!(checkpoint, Checkpoint::from_log(&plog)?);
+    assert_eq!(checkpoint.get("/gone"), None);
+
+    for i in 0..5 {
+        let config = hello_config(&key_manager, &format!("Again {i}!"));
+        checkpoint = update_plog_from(&mut plog, &checkpoint, &config, &mut key_manager)?;
+
+        // the advanced checkpoint matches verifying the whole log
+        assert_eq!(checkpoint, Checkpoint::from_log(&plog)?);
+    }
+    assert_eq!(checkpoint.seqno, 9);
+    assert_eq!(
+        checkpoint.get("/hello/"),
+        Some(&LogValue::Str("Again 4!".to_string()))
+    );
+
+    // deleting a branch drops everything under it
+    append_str(&mut plog, &mut key_manager, "/branch/a", "a")?;
+    append_str(&mut plog, &mut key_manager, "/branch/b", "b")?;
+    checkpoint = Checkpoint::from_log(&plog)?;
+    let config = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap_or_default())
+        .add_op(OpParams::Delete {
+            key: Key::try_from("/branch/")?,
+        })
+        .build();
+    checkpoint = update_plog_from(&mut plog, &checkpoint, &config, &mut key_manager)?;
+    assert_eq!(checkpoint, Checkpoint::from_log(&plog)?);
+    assert_eq!(checkpoint.get("/branch/a"), None);
+    assert_eq!(checkpoint.get("/branch/b"), None);
+
+    // a stale checkpoint is refused
+    let stale = checkpoint.clone();
+    append_str(&mut plog, &mut key_manager, "/hello/", "moved on")?;
+    let config = hello_config(&key_manager, "too late");
+    assert!(update_plog_from(&mut plog, &stale, &config, &mut key_manager).is_err());
+
+    // a snapshot with a key-path that does not parse is refused, not skipped
+    let mut invalid = Checkpoint::from_log(&plog)?;
+    invalid
+        .state
+        .insert("no-slash".to_string(), LogValue::Str("x".to_string()));
+    let config = hello_config(&key_manager, "invalid");
+    assert!(matches!(
+        update_plog_from(&mut plog, &invalid, &config, &mut key_manager),
+        Err(Error::Checkpoint(CheckpointError::InvalidKey(key))) if key == "no-slash"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_resume_checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(3)?;
+    let checkpoint = Checkpoint::from_log(&plog)?;
+
+    // nothing new
+    assert_eq!(checkpoint.resume(&plog)?, checkpoint);
+
+    append_str(&mut plog, &mut key_manager, "/hello/", "later")?;
+    append_str(&mut plog, &mut key_manager, "/other/", "value")?;
+
+    let resumed = checkpoint.resume(&plog)?;
+    assert_eq!(resumed, Checkpoint::from_log(&plog)?);
+    assert_eq!(resumed.seqno, checkpoint.seqno + 2);
+
+    // a snapshot that does not match the log is caught
+    let mut forged = checkpoint.clone();
+    forged
+        .state
+        .insert("/hello/".to_string(), LogValue::Str("forged".to_string()));
+    assert!(forged.resume(&plog).is_err());
+
+    // a checkpoint from another log
+    let (other, _) = generate_updated_plog(3)?;
+    let other_checkpoint = Checkpoint::from_log(&other)?;
+    assert!(other_checkpoint.resume(&plog).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_checkpoint_serde() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(2)?;
+    let checkpoint = Checkpoint::from_log(&plog)?;
+
+    let bytes = serde_ipld_dagcbor::to_vec(&checkpoint)?;
+    let decoded: Checkpoint = serde_ipld_dagcbor::from_slice(&bytes)?;
+    assert_eq!(decoded, checkpoint);
+    assert_eq!(decoded.resume(&plog)?, checkpoint);
+
+    Ok(())
+}
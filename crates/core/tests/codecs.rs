@@ -0,0 +1,135 @@
+//! Create, update and verify plogs with keys of different codecs.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::ops::config::defaults::{DEFAULT_ENTRYKEY, DEFAULT_PUBKEY, DEFAULT_VLAD_KEY};
+use bestsign_core::ops::config::{LockScript, UnlockScript};
+use bestsign_core::ops::open::config::NewLogBuilder;
+use bestsign_core::ops::update::{OpParams, UpdateConfig};
+use bestsign_core::ops::{create, update_plog};
+use bestsign_core::provenance_log::{Key, Log};
+use bestsign_core::utils::key_codec;
+use bestsign_core::{Codec, VerificationReport};
+use fixtures::{init_logger, lock_script, unlock_script, TestKeyManager};
+
+/// Creates a plog with the codecs for the Vlad key, entry key and pubkey, then
+/// updates it with the pubkey
+fn create_and_update(
+    vlad_key: Codec,
+    entrykey: Codec,
+    pubkey: Codec,
+) -> Result<(Log, TestKeyManager), Box<dyn std::error::Error>> {
+    let mut builder = NewLogBuilder::new(LockScript(lock_script()), UnlockScript(unlock_script()));
+    builder
+        .with_vlad_key_codec(vlad_key)
+        .with_entrykey_codec(entrykey)
+        .with_pubkey_codec(pubkey);
+    let config = builder.build();
+
+    let mut key_manager = TestKeyManager::new();
+    let mut plog = create(&config, &mut key_manager)?;
+
+    let update_cfg = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap())
+        .add_op(OpParams::UseStr {
+            key: Key::try_from("/hello/")?,
+            s: "World!".to_string(),
+        })
+        .build();
+    update_plog(&mut plog, &update_cfg, &mut key_manager)?;
+
+    let report = VerificationReport::new(&plog);
+    assert!(
+        report.is_valid(),
+        "{vlad_key:?}/{entrykey:?}/{pubkey:?}: {:?}",
+        report.first_failure()
+    );
+    assert_eq!(key_codec(&plog, DEFAULT_PUBKEY)?, Some(pubkey));
+
+    Ok((plog, key_manager))
+}
+
+#[test]
+fn test_single_codec_plogs() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    for codec in [
+        Codec::Ed25519Priv,
+        Codec::Secp256K1Priv,
+        Codec::P256Priv,
+        Codec::Bls12381G1Priv,
+    ] {
+        create_and_update(codec, codec, codec)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_mixed_codec_plogs() -> Result<(), Box<dyn std::error::Error>> {
+    // a secp256k1 Vlad key with an Ed25519 pubkey
+    create_and_update(Codec::Secp256K1Priv, Codec::Ed25519Priv, Codec::Ed25519Priv)?;
+    // an Ed25519 Vlad key with a secp256k1 pubkey
+    create_and_update(Codec::Ed25519Priv, Codec::Ed25519Priv, Codec::Secp256K1Priv)?;
+    // every key a different codec
+    create_and_update(Codec::P256Priv, Codec::Secp256K1Priv, Codec::Bls12381G1Priv)?;
+    create_and_update(Codec::Bls12381G1Priv, Codec::P256Priv, Codec::Ed25519Priv)?;
+    Ok(())
+}
+
+#[test]
+fn test_rotate_to_another_codec() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) =
+        create_and_update(Codec::Ed25519Priv, Codec::Ed25519Priv, Codec::Ed25519Priv)?;
+
+    // rotate the pubkey to P-256, signed with the Ed25519 pubkey
+    let update_cfg = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap())
+        .add_op(OpParams::KeyGen {
+            key: Key::try_from(DEFAULT_PUBKEY)?,
+            codec: Codec::P256Priv,
+            threshold: 1,
+            limit: 1,
+            revoke: true,
+        })
+        .build();
+    update_plog(&mut plog, &update_cfg, &mut key_manager)?;
+    assert_eq!(key_codec(&plog, DEFAULT_PUBKEY)?, Some(Codec::P256Priv));
+
+    // and sign the next entry with the P-256 pubkey
+    let update_cfg = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap())
+        .add_op(OpParams::UseStr {
+            key: Key::try_from("/hello/")?,
+            s: "P-256".to_string(),
+        })
+        .build();
+    update_plog(&mut plog, &update_cfg, &mut key_manager)?;
+    assert!(VerificationReport::new(&plog).is_valid());
+
+    Ok(())
+}
+
+#[test]
+fn test_key_codec_helpers() -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = NewLogBuilder::new(LockScript(lock_script()), UnlockScript(unlock_script()));
+    builder.with_key_codec(Codec::Secp256K1Priv);
+    let config = builder.build();
+
+    for params in [
+        &config.vlad_params.key.0,
+        &config.entrykey_params,
+        &config.pubkey_params,
+    ] {
+        let OpParams::KeyGen { key, codec, .. } = params else {
+            panic!("not a key generation");
+        };
+        assert!([DEFAULT_VLAD_KEY, DEFAULT_ENTRYKEY, DEFAULT_PUBKEY].contains(&key.as_str()));
+        assert_eq!(*codec, Codec::Secp256K1Priv);
+    }
+
+    let mut key_manager = TestKeyManager::new();
+    let plog = create(&config, &mut key_manager)?;
+    assert_eq!(
+        key_codec(&plog, DEFAULT_PUBKEY)?,
+        Some(Codec::Secp256K1Priv)
+    );
+    assert_eq!(key_codec(&plog, "/nothing")?, None);
+
+    Ok(())
+}
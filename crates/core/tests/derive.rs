@@ -0,0 +1,67 @@
+//! Tests for the deterministic key derivation.
+use bestsign_core::derive::{derive_key, derive_seed, recover_key};
+use bestsign_core::provenance_log::Key;
+use bestsign_core::{Codec, Views};
+
+const SEED: &[u8] = b"an example seed of thirty-two b!";
+
+#[test]
+fn test_derivation_is_deterministic() -> Result<(), Box<dyn std::error::Error>> {
+    let pubkey = Key::try_from("/pubkey")?;
+
+    assert_eq!(
+        derive_seed(SEED, &pubkey, Codec::Ed25519Priv, 0),
+        derive_seed(SEED, &pubkey, Codec::Ed25519Priv, 0)
+    );
+    assert_eq!(
+        derive_key(SEED, &pubkey, Codec::Ed25519Priv, 0)?,
+        derive_key(SEED, &pubkey, Codec::Ed25519Priv, 0)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_derivation_separates_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let pubkey = Key::try_from("/pubkey")?;
+    let recoverykey = Key::try_from("/recoverykey")?;
+
+    let base = derive_seed(SEED, &pubkey, Codec::Ed25519Priv, 0);
+    let others = [
+        derive_seed(SEED, &recoverykey, Codec::Ed25519Priv, 0),
+        derive_seed(SEED, &pubkey, Codec::Secp256K1Priv, 0),
+        derive_seed(SEED, &pubkey, Codec::Ed25519Priv, 1),
+        derive_seed(b"another seed", &pubkey, Codec::Ed25519Priv, 0),
+    ];
+    for other in others {
+        assert_ne!(base, other);
+    }
+
+    // different key-paths get different keys
+    let a = derive_key(SEED, &pubkey, Codec::Ed25519Priv, 0)?;
+    let b = derive_key(SEED, &recoverykey, Codec::Ed25519Priv, 0)?;
+    assert_ne!(
+        a.conv_view()?.to_public_key()?,
+        b.conv_view()?.to_public_key()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_recover_key() -> Result<(), Box<dyn std::error::Error>> {
+    let pubkey = Key::try_from("/pubkey")?;
+    let rotated = derive_key(SEED, &pubkey, Codec::Ed25519Priv, 3)?;
+    let public_key = rotated.conv_view()?.to_public_key()?;
+
+    let (rotation, mk) = recover_key(SEED, &pubkey, &public_key, 10)?.expect("key not found");
+    assert_eq!(rotation, 3);
+    assert_eq!(mk, rotated);
+
+    // not within the rotations tried
+    assert!(recover_key(SEED, &pubkey, &public_key, 2)?.is_none());
+    // not from this seed
+    assert!(recover_key(b"another seed", &pubkey, &public_key, 10)?.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+//! Tests for did:plog DIDs and DID Documents.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    did::{did_from_vlad, resolve_did, vlad_from_did, ResolvedDid, DEFAULT_SERVICE_TYPE},
+    error::DidError,
+    fs_resolver::FsBlockstore,
+    ops::{
+        update::{OpParams, UpdateConfig},
+        update_plog,
+    },
+    provenance_log::Key,
+    resolve::naming::MemoryHeadStore,
+    Base, Codec, EncodedMultikey, EncodedVlad, Error, Views as _,
+};
+use fixtures::{append_str, generate_updated_plog, init_logger, unlock_script};
+
+#[test]
+fn test_did_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(0)?;
+    let did = did_from_vlad(&plog.vlad);
+    assert!(did.starts_with("did:plog:k"));
+    assert_eq!(vlad_from_did(&did)?, plog.vlad);
+
+    assert!(vlad_from_did("did:key:z6Mk").is_err());
+    assert!(vlad_from_did("did:plog:nope").is_err());
+
+    // the same Vlad in another multibase is not the DID
+    let base58 = format!(
+        "did:plog:{}",
+        EncodedVlad::new(Base::Base58Btc, plog.vlad.clone())
+    );
+    assert!(matches!(
+        vlad_from_did(&base58),
+        Err(Error::Did(DidError::InvalidDid(_)))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_method_ids_are_unambiguous() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(0)?;
+    let mut update_cfg = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap());
+    for key in ["/keys/a", "/keys-a"] {
+        update_cfg.add_op(OpParams::KeyGen {
+            key: Key::try_from(key)?,
+            codec: Codec::Ed25519Priv,
+            threshold: 1,
+            limit: 1,
+            revoke: false,
+        });
+    }
+    update_plog(&mut plog, &update_cfg.build(), &mut key_manager)?;
+    append_str(
+        &mut plog,
+        &mut key_manager,
+        "/service/pubkey/endpoint",
+        "https://example.com",
+    )?;
+
+    let doc = ResolvedDid::from_log(&plog)?.did_document;
+    let did = did_from_vlad(&plog.vlad);
+    let ids: Vec<&str> = doc
+        .verification_method
+        .iter()
+        .map(|method| method.id.as_str())
+        .collect();
+    assert!(ids.contains(&format!("{did}#keys-a").as_str()));
+    assert!(ids.contains(&format!("{did}#keys%2Da").as_str()));
+
+    // a service never takes the id of a key
+    assert!(ids.contains(&format!("{did}#pubkey").as_str()));
+    assert_eq!(doc.service.len(), 1);
+    assert_eq!(doc.service[0].id, format!("{did}#service-pubkey-endpoint"));
+    Ok(())
+}
+
+#[test]
+fn test_did_document() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (mut plog, mut key_manager) = generate_updated_plog(1)?;
+
+    // add a recovery key and two services, one with a type
+    let update_cfg = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap())
+        .add_op(OpParams::KeyGen {
+            key: Key::try_from("/recoverykey")?,
+            codec: Codec::Ed25519Priv,
+            threshold: 1,
+            limit: 1,
+            revoke: false,
+        })
+        .build();
+    update_plog(&mut plog, &update_cfg, &mut key_manager)?;
+    append_str(
+        &mut plog,
+        &mut key_manager,
+        "/service/web/endpoint",
+        "https://example.com",
+    )?;
+    append_str(
+        &mut plog,
+        &mut key_manager,
+        "/service/hub/endpoint",
+        "https://hub.example.com",
+    )?;
+    append_str(
+        &mut plog,
+        &mut key_manager,
+        "/service/hub/type",
+        "DIDCommMessaging",
+    )?;
+
+    let resolved = ResolvedDid::from_log(&plog)?;
+    let doc = &resolved.did_document;
+    let did = did_from_vlad(&plog.vlad);
+    assert_eq!(doc.id, did);
+
+    // the one-time keys are left out
+    let ids: Vec<&str> = doc
+        .verification_method
+        .iter()
+        .map(|method| method.id.as_str())
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            format!("{did}#pubkey").as_str(),
+            format!("{did}#recoverykey").as_str()
+        ]
+    );
+
+    let pubkey = key_manager
+        .entry_key()
+        .unwrap()
+        .conv_view()?
+        .to_public_key()?;
+    let method = &doc.verification_method[0];
+    assert_eq!(
+        EncodedMultikey::try_from(method.public_key_multibase.as_str())?.to_inner(),
+        pubkey
+    );
+    assert_eq!(method.controller, did);
+
+    assert_eq!(doc.authentication, vec![format!("{did}#pubkey")]);
+    assert_eq!(doc.assertion_method, vec![format!("{did}#pubkey")]);
+    assert_eq!(
+        doc.capability_invocation,
+        vec![format!("{did}#pubkey"), format!("{did}#recoverykey")]
+    );
+
+    assert_eq!(doc.service.len(), 2);
+    let hub = &doc.service[0];
+    assert_eq!(hub.id, format!("{did}#service-hub-endpoint"));
+    assert_eq!(hub.service_type, "DIDCommMessaging");
+    assert_eq!(hub.service_endpoint, "https://hub.example.com");
+    let web = &doc.service[1];
+    assert_eq!(web.service_type, DEFAULT_SERVICE_TYPE);
+    assert_eq!(web.service_endpoint, "https://example.com");
+
+    // the metadata is the head
+    assert_eq!(resolved.did_document_metadata.version_id, 5);
+
+    // the JSON uses the DID Core names
+    let json = serde_json::to_value(&resolved)?;
+    assert_eq!(
+        json["didDocument"]["@context"][0],
+        "https://www.w3.org/ns/did/v1"
+    );
+    assert_eq!(
+        json["didDocument"]["verificationMethod"][0]["type"],
+        "Multikey"
+    );
+    assert_eq!(json["didDocumentMetadata"]["versionId"], 5);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resolve_did() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("bestsign-did-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+    let heads = MemoryHeadStore::new();
+
+    let (plog, _) = generate_updated_plog(2)?;
+    let did = did_from_vlad(&plog.vlad);
+
+    // no head yet
+    assert!(resolve_did(&did, &heads, blocks.clone()).await.is_err());
+
+    blocks.put_log(&plog)?;
+    heads.set_head(&plog.vlad, &plog.head);
+    let resolved = resolve_did(&did, &heads, blocks.clone()).await?;
+    assert_eq!(resolved, ResolvedDid::from_log(&plog)?);
+    assert_eq!(resolved.did_document_metadata.version_id, 2);
+
+    // an older head gives the older document
+    let prev = plog.entries[&plog.head].prev();
+    heads.set_head(&plog.vlad, &prev);
+    let resolved = resolve_did(&did, &heads, blocks).await?;
+    assert_eq!(resolved.did_document_metadata.version_id, 1);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
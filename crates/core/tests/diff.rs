@@ -0,0 +1,86 @@
+//! Tests for diffing plog states.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    diff::{diff_logs, diff_seqnos},
+    utils::DisplayData,
+};
+use fixtures::{append_str, generate_updated_plog, init_logger};
+
+#[test]
+fn test_diff_logs() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (old, mut key_manager) = generate_updated_plog(2)?;
+    let mut new = old.clone();
+    append_str(&mut new, &mut key_manager, "/hello/", "changed")?;
+    append_str(&mut new, &mut key_manager, "/other/", "added")?;
+
+    let diff = diff_logs(&old, &new)?;
+    assert_eq!(diff.from_seqno, 2);
+    assert_eq!(diff.to_seqno, 4);
+    assert!(diff.removed.is_empty());
+
+    assert_eq!(diff.added.len(), 1);
+    assert!(matches!(
+        &diff.added[0],
+        DisplayData::Str { key_path, value } if key_path.to_string() == "/other/" && value == "added"
+    ));
+
+    assert_eq!(diff.changed.len(), 1);
+    assert!(matches!(
+        &diff.changed[0].old,
+        DisplayData::Str { value, .. } if value == "World 1!"
+    ));
+    assert!(matches!(
+        &diff.changed[0].new,
+        DisplayData::Str { value, .. } if value == "changed"
+    ));
+
+    let seqnos: Vec<u64> = diff.new_entries.iter().map(|entry| entry.seqno).collect();
+    assert_eq!(seqnos, vec![3, 4]);
+
+    // the entries keep the same locks
+    assert!(diff.added_locks.is_empty());
+    assert!(diff.removed_locks.is_empty());
+    assert!(diff.changed_locks.is_empty());
+
+    // going backwards the other key disappears
+    let diff = diff_logs(&new, &old)?;
+    assert_eq!(diff.removed.len(), 1);
+    assert!(diff.added.is_empty());
+    assert!(diff.new_entries.is_empty());
+
+    // nothing changed
+    assert!(diff_logs(&old, &old)?.is_empty());
+
+    // different plogs
+    let (other, _) = generate_updated_plog(2)?;
+    assert!(diff_logs(&old, &other).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_diff_seqnos() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(4)?;
+
+    // /hello/ first appears at seqno 1
+    let diff = diff_seqnos(&plog, 0, 1)?;
+    assert_eq!(diff.added.len(), 1);
+    assert!(diff.changed.is_empty());
+    assert_eq!(diff.new_entries.len(), 1);
+
+    let diff = diff_seqnos(&plog, 1, 4)?;
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.changed.len(), 1);
+    assert!(matches!(
+        &diff.changed[0].new,
+        DisplayData::Str { value, .. } if value == "World 3!"
+    ));
+    assert_eq!(diff.new_entries.len(), 3);
+
+    assert!(diff_seqnos(&plog, 0, 99).is_err());
+
+    Ok(())
+}
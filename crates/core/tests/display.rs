@@ -0,0 +1,235 @@
+//! Tests for the per-entry display timeline.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    ops::{
+        update::{OpParams, UpdateConfig},
+        update_plog,
+    },
+    provenance_log::Key,
+    utils::{
+        get_display_data, get_display_data_with, get_timeline, get_timeline_with, DisplayData,
+        DisplayOptions, OpKind,
+    },
+    Base, EncodedMultikey, VerificationReport, Views as _,
+};
+use fixtures::{append_str, generate_updated_plog, init_logger, unlock_script};
+use provenance_log::{
+    multicodec::Codec,
+    multihash::{mh, EncodedMultihash},
+};
+
+#[test]
+fn test_timeline() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (mut plog, mut key_manager) = generate_updated_plog(4)?;
+    append_str(&mut plog, &mut key_manager, "/other/", "value")?;
+
+    let timeline = get_timeline(&plog)?;
+    assert_eq!(timeline.len(), plog.entries.len());
+
+    // in seqno order, each linking to the one before it
+    for (i, entry) in timeline.iter().enumerate() {
+        assert_eq!(entry.seqno, i as u64);
+        assert!(entry.proof_codec.is_some());
+        if i == 0 {
+            assert!(entry.prev.is_none());
+        } else {
+            assert_eq!(entry.prev.as_ref(), Some(&timeline[i - 1].cid));
+        }
+    }
+
+    // the first entry sets up the keys and locks
+    let first = &timeline[0];
+    assert!(first
+        .ops
+        .iter()
+        .any(|op| op.kind == OpKind::Update && op.key_path.to_string() == "/pubkey"));
+    assert!(first
+        .ops
+        .iter()
+        .any(|op| matches!(op.value, Some(DisplayData::Multikey { .. }))));
+    assert!(!first.locks.is_empty());
+
+    // the last entry wrote a string
+    let last = timeline.last().unwrap();
+    assert_eq!(last.ops.len(), 1);
+    assert_eq!(last.ops[0].key_path.to_string(), "/other/");
+    assert!(matches!(
+        &last.ops[0].value,
+        Some(DisplayData::Str { value, .. }) if value == "value"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_display_options() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, key_manager) = generate_updated_plog(1)?;
+
+    let options = DisplayOptions {
+        base: Base::Base58Btc,
+        fingerprint_codec: Codec::Sha2256,
+        include_bytes: false,
+        include_key_material: true,
+    };
+
+    let DisplayData::ReturnValue { vlad, kvp_data, .. } = get_display_data_with(&plog, &options)?
+    else {
+        panic!("Expected a ReturnValue");
+    };
+    assert!(vlad.encoded.starts_with('z'));
+    assert_eq!(vlad.bytes, None);
+
+    // the default options keep the bytes
+    let DisplayData::ReturnValue { vlad, .. } = get_display_data(&plog)? else {
+        panic!("Expected a ReturnValue");
+    };
+    assert_eq!(vlad.bytes, Some(Vec::<u8>::from(plog.vlad.clone())));
+
+    let pubkey = key_manager
+        .entry_key()
+        .unwrap()
+        .conv_view()?
+        .to_public_key()?;
+    let fingerprint = EncodedMultihash::new(
+        Base::Base58Btc,
+        pubkey.fingerprint_view()?.fingerprint(Codec::Sha2256)?,
+    )
+    .to_string();
+
+    let Some(DisplayData::Multikey {
+        fingerprint: shown,
+        public_key,
+        ..
+    }) = kvp_data.iter().find(|data| {
+        matches!(data, DisplayData::Multikey { key_path, .. } if key_path.to_string() == "/pubkey")
+    })
+    else {
+        panic!("Expected /pubkey to be a Multikey");
+    };
+    assert_eq!(shown, &fingerprint);
+    assert_eq!(
+        public_key.as_deref(),
+        Some(
+            EncodedMultikey::new(Base::Base58Btc, pubkey)
+                .to_string()
+                .as_str()
+        )
+    );
+
+    // no raw bytes anywhere
+    assert!(kvp_data.iter().all(|data| !matches!(
+        data,
+        DisplayData::Vlad { bytes: Some(_), .. } | DisplayData::Data { value: Some(_), .. }
+    )));
+
+    // the timeline follows the options too
+    let timeline = get_timeline_with(&plog, &options)?;
+    assert!(timeline.iter().all(|entry| entry.cid.starts_with('z')));
+
+    // the defaults leave key material out
+    let DisplayData::ReturnValue { kvp_data, .. } = get_display_data(&plog)? else {
+        panic!("Expected a ReturnValue");
+    };
+    assert!(kvp_data.iter().all(|data| !matches!(
+        data,
+        DisplayData::Multikey {
+            public_key: Some(_),
+            ..
+        }
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn test_display_typed_values() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(0)?;
+
+    let mh: Vec<u8> = mh::Builder::new_from_bytes(Codec::Sha2256, b"hello")?
+        .try_build()?
+        .into();
+    // a sha2-256 codec prefix with a payload, which has no display of its own
+    let mut unknown: Vec<u8> = Codec::Sha2256.into();
+    unknown.extend_from_slice(&[1, 2, 3]);
+
+    let config = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap_or_default())
+        .add_op(OpParams::UseBin {
+            key: Key::try_from("/hash")?,
+            data: mh,
+        })
+        .add_op(OpParams::UseBin {
+            key: Key::try_from("/unknown")?,
+            data: unknown.clone(),
+        })
+        .build();
+    update_plog(&mut plog, &config, &mut key_manager)?;
+
+    let DisplayData::ReturnValue { kvp_data, .. } = get_display_data(&plog)? else {
+        panic!("Expected a ReturnValue");
+    };
+    let find = |path: &str| {
+        kvp_data
+            .iter()
+            .find(|data| match data {
+                DisplayData::Multihash { key_path, .. } | DisplayData::Unknown { key_path, .. } => {
+                    key_path.to_string() == path
+                }
+                _ => false,
+            })
+            .cloned()
+    };
+
+    assert!(matches!(
+        find("/hash"),
+        Some(DisplayData::Multihash { codec, .. }) if codec == Codec::Sha2256.to_string()
+    ));
+    assert!(matches!(
+        find("/unknown"),
+        Some(DisplayData::Unknown { bytes, .. }) if bytes.as_deref() == Some(unknown.as_slice())
+    ));
+
+    // without bytes, only the codec is shown
+    let options = DisplayOptions {
+        include_bytes: false,
+        ..DisplayOptions::default()
+    };
+    let DisplayData::ReturnValue { kvp_data, .. } = get_display_data_with(&plog, &options)? else {
+        panic!("Expected a ReturnValue");
+    };
+    assert!(kvp_data.iter().any(|data| matches!(
+        data,
+        DisplayData::Unknown { key_path, bytes: None, .. } if key_path.to_string() == "/unknown"
+    )));
+
+    // the unlock script shows its source
+    let timeline = get_timeline(&plog)?;
+    let DisplayData::Script { source, .. } = &timeline.last().unwrap().unlock else {
+        panic!("Expected the unlock to be a Script");
+    };
+    assert!(source.as_deref().unwrap().contains("/entry/proof"));
+
+    Ok(())
+}
+
+#[test]
+fn test_display_refuses_unverified_log() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, _) = generate_updated_plog(1)?;
+
+    // an entry signed with a key the plog does not know
+    let (_, mut other_keys) = generate_updated_plog(0)?;
+    let config = UpdateConfig::new(unlock_script(), other_keys.entry_key().unwrap())
+        .add_op(OpParams::UseStr {
+            key: Key::try_from("/hello/")?,
+            s: "forged".to_string(),
+        })
+        .build();
+    update_plog(&mut plog, &config, &mut other_keys)?;
+    assert!(!VerificationReport::new(&plog).is_valid());
+
+    assert!(get_display_data(&plog).is_err());
+
+    Ok(())
+}
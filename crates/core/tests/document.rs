@@ -0,0 +1,129 @@
+//! Tests for detached document signatures.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    error::DocumentError,
+    fs_resolver::FsBlockstore,
+    ops::{
+        config::defaults::DEFAULT_PUBKEY,
+        update::{OpParams, UpdateConfig},
+        update_plog,
+    },
+    provenance_log::Key,
+    Codec, DetachedSignature, Error,
+};
+use fixtures::{generate_updated_plog, init_logger, unlock_script};
+
+const DOCUMENT: &[u8] = b"I, the holder of this plog, agree.";
+
+#[test]
+fn test_sign_and_verify() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (plog, key_manager) = generate_updated_plog(2)?;
+    let pubkey = Key::try_from(DEFAULT_PUBKEY)?;
+    let signing_key = key_manager.entry_key().unwrap();
+
+    let signature = DetachedSignature::sign(&plog, &pubkey, &signing_key, DOCUMENT, &key_manager)?;
+    assert_eq!(signature.vlad, plog.vlad);
+    assert_eq!(signature.head, plog.head);
+    assert_eq!(signature.verify_with_log(&plog, DOCUMENT)?, 2);
+
+    // roundtrip through the compact encoding
+    let bytes: Vec<u8> = signature.clone().into();
+    let decoded = DetachedSignature::try_from(bytes.as_slice())?;
+    assert_eq!(decoded, signature);
+    assert!(DetachedSignature::try_from(&bytes[..bytes.len() - 1]).is_err());
+
+    // another document
+    assert!(matches!(
+        signature.verify_with_log(&plog, b"I do not agree."),
+        Err(Error::Document(DocumentError::HashMismatch))
+    ));
+
+    // another key-path
+    let mut forged = signature.clone();
+    forged.key_path = Key::try_from("/entrykey")?;
+    assert!(forged.verify_with_log(&plog, DOCUMENT).is_err());
+
+    // another plog
+    let (other, _) = generate_updated_plog(2)?;
+    assert!(matches!(
+        signature.verify_with_log(&other, DOCUMENT),
+        Err(Error::Document(DocumentError::VladMismatch))
+    ));
+
+    // a key that is not the /pubkey
+    let (_, other_keys) = generate_updated_plog(0)?;
+    assert!(matches!(
+        DetachedSignature::sign(
+            &plog,
+            &pubkey,
+            &other_keys.entry_key().unwrap(),
+            DOCUMENT,
+            &key_manager
+        ),
+        Err(Error::Document(DocumentError::KeyMismatch(_)))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_signature_survives_rotation() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(1)?;
+    let pubkey = Key::try_from(DEFAULT_PUBKEY)?;
+    let old_key = key_manager.entry_key().unwrap();
+    let signature = DetachedSignature::sign(&plog, &pubkey, &old_key, DOCUMENT, &key_manager)?;
+
+    let update_cfg = UpdateConfig::new(unlock_script(), old_key.clone())
+        .add_op(OpParams::KeyGen {
+            key: pubkey.clone(),
+            codec: Codec::Ed25519Priv,
+            threshold: 1,
+            limit: 1,
+            revoke: true,
+        })
+        .build();
+    update_plog(&mut plog, &update_cfg, &mut key_manager)?;
+
+    // still valid at the entry it was made at
+    assert_eq!(signature.verify_with_log(&plog, DOCUMENT)?, 1);
+
+    // but the old key signs nothing new
+    assert!(DetachedSignature::sign(&plog, &pubkey, &old_key, DOCUMENT, &key_manager).is_err());
+    let new_key = key_manager.entry_key().unwrap();
+    let signature = DetachedSignature::sign(&plog, &pubkey, &new_key, DOCUMENT, &key_manager)?;
+    assert_eq!(signature.verify_with_log(&plog, DOCUMENT)?, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_with_resolver() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("bestsign-document-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+
+    let (plog, key_manager) = generate_updated_plog(3)?;
+    let signature = DetachedSignature::sign_with_hash(
+        &plog,
+        &Key::try_from(DEFAULT_PUBKEY)?,
+        &key_manager.entry_key().unwrap(),
+        DOCUMENT,
+        Codec::Blake3,
+        &key_manager,
+    )?;
+
+    // nothing to resolve yet
+    assert!(matches!(
+        signature.verify(DOCUMENT, blocks.clone()).await,
+        Err(Error::Document(DocumentError::Unresolved(_)))
+    ));
+
+    blocks.put_log(&plog)?;
+    assert_eq!(signature.verify(DOCUMENT, blocks.clone()).await?, 3);
+    assert!(signature.verify(b"tampered", blocks).await.is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
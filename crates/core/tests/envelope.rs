@@ -0,0 +1,76 @@
+//! Tests for the plog interchange envelope.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::envelope::{decode_log, encode_log, Envelope, EnvelopeKind, PartialLog, MAGIC};
+use fixtures::{generate_updated_plog, init_logger};
+
+#[test]
+fn test_envelope_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (plog, _) = generate_updated_plog(3)?;
+
+    // a whole log
+    let bytes = encode_log(&plog);
+    assert!(bytes.starts_with(MAGIC));
+    assert_eq!(decode_log(&bytes)?, plog);
+
+    // a range of entries
+    let partial = PartialLog::from_log(&plog, 1..=2);
+    assert_eq!(
+        partial
+            .entries
+            .iter()
+            .map(|e| e.seqno())
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    let envelope = Envelope::Partial(partial);
+    let decoded = Envelope::decode(&envelope.encode())?;
+    assert_eq!(decoded, envelope);
+    assert_eq!(decoded.kind(), EnvelopeKind::Partial);
+
+    // a single entry
+    let envelope = Envelope::Entry(plog.entries[&plog.head].clone());
+    let bytes = envelope.encode();
+    assert_eq!(Envelope::decode(&bytes)?, envelope);
+
+    // an entry is not a log
+    assert!(decode_log(&bytes).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_envelope_legacy_and_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(1)?;
+
+    // the bare binary log
+    let bare: Vec<u8> = plog.clone().into();
+    assert_eq!(decode_log(&bare)?, plog);
+
+    // the serde_cbor blobs of older core-bindings
+    let cbor = serde_cbor::to_vec(&plog)?;
+    assert_eq!(decode_log(&cbor)?, plog);
+
+    // garbage
+    assert!(decode_log(b"not a plog").is_err());
+    assert!(Envelope::decode(b"not a plog").is_err());
+
+    // an unknown version or kind
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&[2, 0]);
+    assert!(Envelope::decode(&bytes).is_err());
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&[1, 9]);
+    assert!(Envelope::decode(&bytes).is_err());
+
+    // the version 1 as a non-minimal varint
+    let encoded = encode_log(&plog);
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&[0x81, 0x00]);
+    bytes.extend_from_slice(&encoded[MAGIC.len() + 1..]);
+    assert!(Envelope::decode(&bytes).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Tests for equivocation fraud proofs.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    fs_resolver::FsBlockstore,
+    resolve::{choose_head, resolve_plog, ForkChoicePolicy},
+    EquivocationProof, Multikey, Views as _,
+};
+use fixtures::{append_str, generate_updated_plog, init_logger};
+
+#[tokio::test]
+async fn test_equivocation_proof() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let dir = std::env::temp_dir().join(format!("bestsign-equiv-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+
+    // two different entries signed by the same /pubkey on top of the same head
+    let (base, mut key_manager) = generate_updated_plog(1)?;
+    let mut left = base.clone();
+    let mut right = base.clone();
+    append_str(&mut left, &mut key_manager, "/hello/", "left")?;
+    append_str(&mut right, &mut key_manager, "/hello/", "right")?;
+
+    blocks.put_log(&left)?;
+    blocks.put_log(&right)?;
+
+    let left_resolved = resolve_plog(&left.vlad, &left.head, blocks.clone()).await?;
+    let right_resolved = resolve_plog(&right.vlad, &right.head, blocks.clone()).await?;
+
+    let proof = EquivocationProof::from_resolved(&left_resolved, &right_resolved)?;
+    assert_eq!(proof.seqno(), 2);
+    assert_eq!(proof.ancestor, base.head);
+    assert_eq!(proof.first.cid(), left.head);
+    assert_eq!(proof.second.cid(), right.head);
+    proof.verify_with_log(&base)?;
+    proof.verify_with_log(&left)?;
+
+    // both signed by the /pubkey
+    let pubkey: Multikey = key_manager
+        .entry_key()
+        .unwrap()
+        .conv_view()?
+        .to_public_key()?;
+    assert_eq!(proof.first_signer, pubkey);
+    proof.verify_with_keys(&[pubkey.clone()])?;
+    assert!(proof.verify_with_keys(&[]).is_err());
+
+    // roundtrip through the compact encoding
+    let bytes: Vec<u8> = proof.clone().into();
+    let decoded = EquivocationProof::try_from(bytes.as_slice())?;
+    assert_eq!(decoded, proof);
+    decoded.verify_with_log(&base)?;
+    assert!(EquivocationProof::try_from(&bytes[..bytes.len() - 1]).is_err());
+
+    // the same proofs come out of a fork choice
+    let choice = choose_head(
+        &left.vlad,
+        &[left.head.clone(), right.head.clone()],
+        blocks.clone(),
+        ForkChoicePolicy::FirstSeen,
+    )
+    .await?;
+    let proofs = EquivocationProof::from_fork_choice(&choice);
+    assert_eq!(proofs, vec![proof.clone()]);
+
+    // a log that is merely behind is not a fork
+    let base_resolved = resolve_plog(&base.vlad, &base.head, blocks.clone()).await?;
+    assert!(EquivocationProof::from_resolved(&base_resolved, &left_resolved).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampered_equivocation_proof() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("bestsign-equiv-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+
+    let (base, mut key_manager) = generate_updated_plog(0)?;
+    let mut left = base.clone();
+    let mut right = base.clone();
+    append_str(&mut left, &mut key_manager, "/hello/", "left")?;
+    append_str(&mut right, &mut key_manager, "/hello/", "right")?;
+    blocks.put_log(&left)?;
+    blocks.put_log(&right)?;
+
+    let left_resolved = resolve_plog(&left.vlad, &left.head, blocks.clone()).await?;
+    let right_resolved = resolve_plog(&right.vlad, &right.head, blocks.clone()).await?;
+    let proof = EquivocationProof::from_resolved(&left_resolved, &right_resolved)?;
+
+    // an unrelated signer does not verify
+    let (_, other_key_manager) = generate_updated_plog(0)?;
+    let mut forged = proof.clone();
+    forged.second_signer = other_key_manager
+        .entry_key()
+        .unwrap()
+        .conv_view()?
+        .to_public_key()?;
+    assert!(forged.verify_with_log(&base).is_err());
+
+    // signers embedded in the proof are not trusted on their own
+    assert!(forged
+        .verify_with_keys(&[forged.second_signer.clone()])
+        .is_err());
+    let (other, _) = generate_updated_plog(0)?;
+    assert!(proof.verify_with_log(&other).is_err());
+
+    // the same entry twice is not equivocation
+    let mut forged = proof.clone();
+    forged.second = forged.first.clone();
+    assert!(forged.verify_with_log(&base).is_err());
+
+    // a different ancestor is not equivocation
+    let mut forged = proof.clone();
+    forged.ancestor = left.head.clone();
+    assert!(forged.verify_with_log(&left).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use bestsign_core::ops::config::defaults::{DEFAULT_PUBKEY, DEFAULT_VLAD_KEY};
 use bestsign_core::ops::config::{LockScript, UnlockScript};
 use bestsign_core::ops::open::config::NewLogBuilder;
-use bestsign_core::ops::{create, CryptoManager};
+use bestsign_core::ops::update::{OpParams, UpdateConfig};
+use bestsign_core::ops::{create, update_plog, CryptoManager};
 use bestsign_core::Error;
 
 use provenance_log::{multibase, multicodec, multihash, multikey, multisig};
@@ -149,3 +150,47 @@ pub fn generate_plog() -> Result<Log, Box<dyn std::error::Error>> {
 
     Ok(create(&config, &mut key_manager)?)
 }
+
+/// Makes a new plog and appends `updates` entries to it, each setting `/hello/` to a new value.
+///
+/// Returns the [Log] along with the [TestKeyManager] holding the `/pubkey` signing key.
+pub fn generate_updated_plog(
+    updates: usize,
+) -> Result<(Log, TestKeyManager), Box<dyn std::error::Error>> {
+    let config =
+        NewLogBuilder::new(LockScript(lock_script()), UnlockScript(unlock_script())).build();
+
+    let mut key_manager = TestKeyManager::new();
+
+    let mut plog = create(&config, &mut key_manager)?;
+
+    for i in 0..updates {
+        append_str(
+            &mut plog,
+            &mut key_manager,
+            "/hello/",
+            &format!("World {i}!"),
+        )?;
+    }
+
+    Ok((plog, key_manager))
+}
+
+/// Appends an entry setting the given key-path to the string value, signed with the `/pubkey`
+pub fn append_str(
+    plog: &mut Log,
+    key_manager: &mut TestKeyManager,
+    key: &str,
+    value: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let update_cfg =
+        UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap_or_default())
+            .add_op(OpParams::UseStr {
+                key: Key::try_from(key)?,
+                s: value.to_string(),
+            })
+            .build();
+
+    update_plog(plog, &update_cfg, key_manager)?;
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Tests for choosing among several heads for the same Vlad.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    fs_resolver::FsBlockstore,
+    resolve::{
+        choose_head,
+        fork_choice::{relation, Outcome, Relation},
+        ForkChoicePolicy,
+    },
+};
+use fixtures::{append_str, generate_updated_plog, init_logger};
+
+#[tokio::test]
+async fn test_fork_choice() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let dir = std::env::temp_dir().join(format!("bestsign-fork-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+
+    // a shared prefix of two entries, then two different third entries
+    let (base, mut key_manager) = generate_updated_plog(1)?;
+    let mut left = base.clone();
+    let mut right = base.clone();
+    append_str(&mut left, &mut key_manager, "/hello/", "left")?;
+    append_str(&mut right, &mut key_manager, "/hello/", "right")?;
+    // make the right side longer
+    append_str(&mut right, &mut key_manager, "/hello/", "right again")?;
+
+    blocks.put_log(&left)?;
+    blocks.put_log(&right)?;
+
+    // the fork is at seqno 2, after the shared head of the base
+    let Relation::Fork {
+        seqno,
+        ancestor,
+        ours,
+        theirs,
+    } = relation(&left, &right)
+    else {
+        panic!("Expected a fork");
+    };
+    assert_eq!(seqno, 2);
+    assert_eq!(ancestor, Some(base.head.clone()));
+    assert_eq!(ours, left.head);
+    assert_ne!(ours, theirs);
+
+    assert_eq!(relation(&base, &left), Relation::Behind);
+    assert_eq!(relation(&left, &base), Relation::Ahead);
+    assert_eq!(relation(&left, &left), Relation::Same);
+
+    let heads = [base.head.clone(), left.head.clone(), right.head.clone()];
+
+    // longest valid picks the right side, the left side is a fork and base is behind
+    let choice = choose_head(
+        &left.vlad,
+        &heads,
+        blocks.clone(),
+        ForkChoicePolicy::LongestValid,
+    )
+    .await?;
+    assert_eq!(choice.head, right.head);
+    assert_eq!(choice.winner.log, right);
+    assert!(choice.has_fork());
+    assert_eq!(choice.losers.len(), 2);
+    assert!(matches!(
+        choice.losers[0].outcome,
+        Outcome::Valid {
+            relation: Relation::Behind,
+            ..
+        }
+    ));
+    assert!(matches!(
+        choice.losers[1].outcome,
+        Outcome::Valid {
+            relation: Relation::Fork { seqno: 2, .. },
+            ..
+        }
+    ));
+
+    // first seen picks the first valid candidate
+    let choice = choose_head(
+        &left.vlad,
+        &heads,
+        blocks.clone(),
+        ForkChoicePolicy::FirstSeen,
+    )
+    .await?;
+    assert_eq!(choice.head, base.head);
+    assert!(!choice.has_fork());
+
+    // lowest cost never prefers a log that is behind the winner
+    let choice = choose_head(
+        &left.vlad,
+        &heads,
+        blocks.clone(),
+        ForkChoicePolicy::LowestCost,
+    )
+    .await?;
+    assert_ne!(choice.head, base.head);
+    assert_eq!(choice.losers.len(), 2);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fork_choice_invalid_candidates() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("bestsign-fork-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(1)?;
+    let (other, _) = generate_updated_plog(1)?;
+    blocks.put_log(&plog)?;
+
+    // the other head is not in the store, and is listed twice
+    let heads = [other.head.clone(), plog.head.clone(), other.head.clone()];
+    let choice = choose_head(
+        &plog.vlad,
+        &heads,
+        blocks.clone(),
+        ForkChoicePolicy::default(),
+    )
+    .await?;
+    assert_eq!(choice.head, plog.head);
+    assert_eq!(choice.losers.len(), 1);
+    assert!(matches!(choice.losers[0].outcome, Outcome::Invalid(_)));
+
+    // nothing valid at all
+    assert!(choose_head(
+        &plog.vlad,
+        &[other.head.clone()],
+        blocks,
+        ForkChoicePolicy::default()
+    )
+    .await
+    .is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
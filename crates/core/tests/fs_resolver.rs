@@ -0,0 +1,167 @@
+//! Tests for the directory backed block store.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use std::path::PathBuf;
+use std::{future::Future, pin::Pin};
+
+use bestsign_core::{
+    fs_resolver::{FsBlockstore, FsBlockstoreError},
+    resolve::{publish_plog, resolve_plog, Resolver},
+    Cid,
+};
+use fixtures::{generate_updated_plog, init_logger};
+
+/// A fresh, empty directory under the system temp dir
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bestsign-{name}-{}", rand::random::<u64>()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn test_publish_and_resolve() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let dir = temp_dir("fs-publish");
+    let store = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(3)?;
+
+    publish_plog(&plog, &store).await?;
+
+    // first lock plus one file per entry
+    assert_eq!(std::fs::read_dir(&dir)?.count(), plog.entries.len() + 1);
+    assert!(store.has(&plog.head));
+
+    let resolved = resolve_plog(&plog.vlad, &plog.head, store.clone()).await?;
+    assert_eq!(resolved.log, plog);
+    assert_eq!(resolved.verification_counts.len(), 4);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_put_log_is_idempotent() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = temp_dir("fs-idempotent");
+    let store = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(1)?;
+
+    store.put_log(&plog)?;
+    store.put_log(&plog)?;
+
+    let resolved = resolve_plog(&plog.vlad, &plog.head, store).await?;
+    assert_eq!(resolved.log, plog);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampered_block_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = temp_dir("fs-tampered");
+    let store = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(1)?;
+    store.put_log(&plog)?;
+
+    // overwrite the head block with some other entry's bytes
+    let foot_bytes: Vec<u8> = plog.entries[&plog.foot].clone().into();
+    std::fs::write(store.block_path(&plog.head), foot_bytes)?;
+
+    assert!(matches!(
+        store.resolve(&plog.head).await,
+        Err(FsBlockstoreError::InvalidBlock(_))
+    ));
+    assert!(resolve_plog(&plog.vlad, &plog.head, store).await.is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_block() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = temp_dir("fs-missing");
+    let store = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(0)?;
+
+    assert!(!store.has(&plog.head));
+    assert!(matches!(
+        store.get(&plog.head),
+        Err(FsBlockstoreError::BlockNotFound(_))
+    ));
+
+    // putting a block under the wrong cid is refused
+    let foot_bytes: Vec<u8> = plog.entries[&plog.foot].clone().into();
+    assert!(store.put(plog.vlad.cid(), &foot_bytes).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Serves the blocks of the store, but other bytes for the first lock
+#[derive(Clone)]
+struct SubstitutedFirstLock {
+    store: FsBlockstore,
+    first_lock_cid: Cid,
+    first_lock: Vec<u8>,
+}
+
+impl Resolver for SubstitutedFirstLock {
+    type Error = FsBlockstoreError;
+
+    fn resolve(
+        &self,
+        cid: &Cid,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::Error>> + Send>> {
+        if *cid == self.first_lock_cid {
+            let first_lock = self.first_lock.clone();
+            return Box::pin(async move { Ok(first_lock) });
+        }
+        self.store.resolve(cid)
+    }
+}
+
+#[tokio::test]
+async fn test_substituted_first_lock_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = temp_dir("fs-first-lock");
+    let store = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(1)?;
+    store.put_log(&plog)?;
+
+    let resolver = SubstitutedFirstLock {
+        store,
+        first_lock_cid: plog.vlad.cid().clone(),
+        first_lock: b"check_signature(\"/entrykey\", \"/entry/\")".to_vec(),
+    };
+    assert!(resolve_plog(&plog.vlad, &plog.head, resolver)
+        .await
+        .is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encoded_first_lock_is_accepted() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = temp_dir("fs-encoded-first-lock");
+    let store = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(1)?;
+    store.put_log(&plog)?;
+
+    // published as the encoded script, the way older publishers stored it
+    let resolver = SubstitutedFirstLock {
+        store,
+        first_lock_cid: plog.vlad.cid().clone(),
+        first_lock: plog.first_lock.clone().into(),
+    };
+    let resolved = resolve_plog(&plog.vlad, &plog.head, resolver).await?;
+    assert_eq!(resolved.log, plog);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! Golden vectors: plogs created and updated from fixed seeds must never change.
+//!
+//! Run with `BESTSIGN_BLESS=1` to (re)generate the files in `tests/golden`, and
+//! review the diff before committing them.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::derive::SeededKeyManager;
+use bestsign_core::envelope::{decode_log, encode_log};
+use bestsign_core::ops::config::defaults::DEFAULT_PUBKEY;
+use bestsign_core::ops::config::{LockScript, UnlockScript};
+use bestsign_core::ops::open::config::NewLogBuilder;
+use bestsign_core::ops::update::{OpParams, UpdateConfig};
+use bestsign_core::ops::{create_from_seed, update_plog};
+use bestsign_core::provenance_log::{Key, Log};
+use bestsign_core::{multicid::EncodedCid, Base, Codec, EncodedVlad, VerificationReport};
+use fixtures::{lock_script, unlock_script};
+use std::path::PathBuf;
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn new_plog(seed: &[u8]) -> Result<(Log, SeededKeyManager), Box<dyn std::error::Error>> {
+    let config =
+        NewLogBuilder::new(LockScript(lock_script()), UnlockScript(unlock_script())).build();
+    Ok(create_from_seed(&config, seed)?)
+}
+
+fn update(
+    plog: &mut Log,
+    key_manager: &mut SeededKeyManager,
+    op: OpParams,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signing_key = key_manager.key(DEFAULT_PUBKEY).cloned().unwrap_or_default();
+    let config = UpdateConfig::new(unlock_script(), signing_key)
+        .add_op(op)
+        .build();
+    update_plog(plog, &config, key_manager)?;
+    Ok(())
+}
+
+/// The golden vectors, by name
+fn vectors() -> Result<Vec<(&'static str, Log)>, Box<dyn std::error::Error>> {
+    let (created, _) = new_plog(b"bestsign golden vector: created")?;
+
+    let (mut updated, mut key_manager) = new_plog(b"bestsign golden vector: updated")?;
+    for i in 0..3 {
+        update(
+            &mut updated,
+            &mut key_manager,
+            OpParams::UseStr {
+                key: Key::try_from("/hello/")?,
+                s: format!("World {i}!"),
+            },
+        )?;
+    }
+
+    let (mut rotated, mut key_manager) = new_plog(b"bestsign golden vector: rotated")?;
+    update(
+        &mut rotated,
+        &mut key_manager,
+        OpParams::KeyGen {
+            key: Key::try_from(DEFAULT_PUBKEY)?,
+            codec: Codec::Ed25519Priv,
+            threshold: 1,
+            limit: 1,
+            revoke: true,
+        },
+    )?;
+    update(
+        &mut rotated,
+        &mut key_manager,
+        OpParams::UseStr {
+            key: Key::try_from("/hello/")?,
+            s: "signed with the rotated key".to_string(),
+        },
+    )?;
+
+    Ok(vec![
+        ("created", created),
+        ("updated", updated),
+        ("rotated", rotated),
+    ])
+}
+
+fn metadata(log: &Log) -> serde_json::Value {
+    serde_json::json!({
+        "vlad": EncodedVlad::new(Base::Base36Lower, log.vlad.clone()).to_string(),
+        "head": EncodedCid::new(Base::Base32Lower, log.head.clone()).to_string(),
+    })
+}
+
+#[test]
+fn test_golden_vectors() -> Result<(), Box<dyn std::error::Error>> {
+    let bless = std::env::var("BESTSIGN_BLESS").is_ok_and(|v| v == "1");
+    let dir = golden_dir();
+
+    for (name, log) in vectors()? {
+        assert!(VerificationReport::new(&log).is_valid());
+        let bytes = encode_log(&log);
+        let plog_path = dir.join(format!("{name}.plog"));
+        let meta_path = dir.join(format!("{name}.json"));
+
+        if bless {
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(&plog_path, &bytes)?;
+            std::fs::write(&meta_path, serde_json::to_string_pretty(&metadata(&log))?)?;
+            continue;
+        }
+
+        let expected = std::fs::read(&plog_path).unwrap_or_else(|_| {
+            panic!("Missing golden vector {name}, run the tests with BESTSIGN_BLESS=1")
+        });
+        let meta: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&meta_path)?)?;
+
+        assert_eq!(metadata(&log), meta, "golden vector {name} changed");
+        assert!(bytes == expected, "golden vector {name} changed");
+
+        // the checked-in plog still decodes and verifies
+        let golden = decode_log(&expected)?;
+        assert_eq!(metadata(&golden), meta);
+        assert!(VerificationReport::new(&golden).is_valid());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_seeded_create_is_reproducible() -> Result<(), Box<dyn std::error::Error>> {
+    let (a, mut a_keys) = new_plog(b"same seed")?;
+    let (b, mut b_keys) = new_plog(b"same seed")?;
+    assert_eq!(encode_log(&a), encode_log(&b));
+
+    let (c, _) = new_plog(b"other seed")?;
+    assert_ne!(a.vlad, c.vlad);
+
+    // updates too
+    let (mut a, mut b) = (a, b);
+    let op = OpParams::UseStr {
+        key: Key::try_from("/hello/")?,
+        s: "World!".to_string(),
+    };
+    update(&mut a, &mut a_keys, op.clone())?;
+    update(&mut b, &mut b_keys, op)?;
+    assert_eq!(encode_log(&a), encode_log(&b));
+
+    // every key-path gets its own key
+    assert_ne!(a_keys.key(DEFAULT_PUBKEY), a_keys.key("/entrykey"));
+    assert_eq!(a_keys.next_rotation(DEFAULT_PUBKEY), 1);
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Tests for time travel queries over plog state.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    history::{key_history, state_at_cid, state_at_seqno, states, ChangeKind, KeyValidity},
+    ops::{
+        config::defaults::DEFAULT_PUBKEY,
+        update::{OpParams, UpdateConfig},
+        update_plog,
+    },
+    provenance_log::{Key, Log, LogValue},
+    Checkpoint, Codec, Multikey, Views as _,
+};
+use fixtures::{append_str, generate_updated_plog, init_logger, unlock_script, TestKeyManager};
+
+#[test]
+fn test_state_at() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (mut plog, mut key_manager) = generate_updated_plog(4)?;
+    append_str(&mut plog, &mut key_manager, "/other/", "value")?;
+
+    let all = states(&plog)?;
+    assert_eq!(all.len(), plog.entries.len());
+    assert_eq!(all.last(), Some(&Checkpoint::from_log(&plog)?));
+
+    // /hello/ did not exist in the first entry
+    let first = state_at_seqno(&plog, 0)?;
+    assert_eq!(first.get("/hello/"), None);
+    assert!(first.get("/pubkey").is_some());
+
+    // the value written by seqno 3 is the fourth write
+    let third = state_at_seqno(&plog, 3)?;
+    assert_eq!(
+        third.get("/hello/"),
+        Some(&LogValue::Str("World 2!".to_string()))
+    );
+    assert_eq!(third.get("/other/"), None);
+    assert_eq!(third, all[3]);
+
+    // the same state by Cid
+    assert_eq!(state_at_cid(&plog, &third.cid)?, third);
+
+    // the pubkey never changed
+    assert_eq!(first.get("/pubkey"), third.get("/pubkey"));
+
+    assert!(state_at_seqno(&plog, 99).is_err());
+    let (other, _) = generate_updated_plog(1)?;
+    assert!(state_at_cid(&plog, &other.head).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_key_history() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(3)?;
+    append_str(&mut plog, &mut key_manager, "/other/", "value")?;
+    append_str(&mut plog, &mut key_manager, "/hello/", "last")?;
+
+    let history = key_history(&plog, "/hello/")?;
+    let seqnos: Vec<u64> = history.iter().map(|change| change.seqno).collect();
+    assert_eq!(seqnos, vec![1, 2, 3, 5]);
+    assert!(history
+        .iter()
+        .all(|change| change.kind == ChangeKind::Update));
+    assert_eq!(
+        history[0].value,
+        Some(LogValue::Str("World 0!".to_string()))
+    );
+    assert_eq!(history[3].value, Some(LogValue::Str("last".to_string())));
+    assert_eq!(history[3].cid, plog.head);
+
+    // /pubkey was only set by the first entry
+    let history = key_history(&plog, "/pubkey")?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].seqno, 0);
+
+    assert!(key_history(&plog, "/missing")?.is_empty());
+
+    // a branch includes the key-paths under it, and a branch delete ends them all
+    append_str(&mut plog, &mut key_manager, "/hello/sub", "child")?;
+    apply(
+        &mut plog,
+        &mut key_manager,
+        OpParams::Delete {
+            key: Key::try_from("/hello/")?,
+        },
+    )?;
+    let history = key_history(&plog, "/hello/")?;
+    let seqnos: Vec<u64> = history.iter().map(|change| change.seqno).collect();
+    assert_eq!(seqnos, vec![1, 2, 3, 5, 6, 7]);
+    assert_eq!(history[4].key_path, "/hello/sub");
+    assert_eq!(history[5].kind, ChangeKind::Delete);
+
+    let history = key_history(&plog, "/hello/sub")?;
+    let changes: Vec<(u64, ChangeKind, &str)> = history
+        .iter()
+        .map(|change| (change.seqno, change.kind, change.key_path.as_str()))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (6, ChangeKind::Update, "/hello/sub"),
+            (7, ChangeKind::Delete, "/hello/")
+        ]
+    );
+
+    Ok(())
+}
+
+/// Appends an entry with the op, signed with the current `/pubkey`
+fn apply(
+    plog: &mut Log,
+    key_manager: &mut TestKeyManager,
+    op: OpParams,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let update_cfg = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap())
+        .add_op(op)
+        .build();
+    update_plog(plog, &update_cfg, key_manager)?;
+    Ok(())
+}
+
+fn keygen(key: &str, revoke: bool) -> Result<OpParams, Box<dyn std::error::Error>> {
+    Ok(OpParams::KeyGen {
+        key: Key::try_from(key)?,
+        codec: Codec::Ed25519Priv,
+        threshold: 1,
+        limit: 1,
+        revoke,
+    })
+}
+
+fn public_key(mk: &Multikey) -> Result<Multikey, Box<dyn std::error::Error>> {
+    Ok(mk.conv_view()?.to_public_key()?)
+}
+
+#[test]
+fn test_key_validity() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(1)?;
+    let first = public_key(&key_manager.entry_key().unwrap())?;
+
+    // seqno 2 rotates with a revoke, seqno 3 replaces the key without one
+    apply(&mut plog, &mut key_manager, keygen(DEFAULT_PUBKEY, true)?)?;
+    let second = public_key(&key_manager.entry_key().unwrap())?;
+    apply(&mut plog, &mut key_manager, keygen(DEFAULT_PUBKEY, false)?)?;
+    let third = public_key(&key_manager.entry_key().unwrap())?;
+
+    // seqno 4 sets a backup key and seqno 5 deletes it
+    apply(&mut plog, &mut key_manager, keygen("/backup", false)?)?;
+    apply(
+        &mut plog,
+        &mut key_manager,
+        OpParams::Delete {
+            key: Key::try_from("/backup")?,
+        },
+    )?;
+
+    let validity = KeyValidity::new(&plog)?;
+
+    let intervals = validity.intervals(DEFAULT_PUBKEY);
+    let spans: Vec<(u64, Option<u64>)> = intervals.iter().map(|i| (i.from, i.until)).collect();
+    assert_eq!(spans, vec![(0, Some(2)), (2, Some(3)), (3, None)]);
+    assert_eq!(intervals[0].key, first);
+    assert_eq!(intervals[1].key, second);
+    assert_eq!(intervals[2].key, third);
+
+    assert!(validity.was_valid(DEFAULT_PUBKEY, &first, 0));
+    assert!(validity.was_valid(DEFAULT_PUBKEY, &first, 1));
+    assert!(!validity.was_valid(DEFAULT_PUBKEY, &first, 2));
+    assert!(validity.was_valid(DEFAULT_PUBKEY, &second, 2));
+    assert!(!validity.was_valid(DEFAULT_PUBKEY, &second, 3));
+    assert!(validity.was_valid(DEFAULT_PUBKEY, &third, 5));
+    assert!(!validity.was_valid("/backup", &third, 5));
+
+    let backup = validity.intervals("/backup");
+    assert_eq!(backup.len(), 1);
+    assert_eq!((backup[0].from, backup[0].until), (4, Some(5)));
+    assert!(validity.key_at("/backup", 4).is_some());
+    assert!(validity.key_at("/backup", 5).is_none());
+
+    // strings are not keys
+    assert!(validity.intervals("/hello/").is_empty());
+    assert!(validity.key_paths().any(|key_path| key_path == "/backup"));
+
+    // seqno 6 sets a key under a branch and seqno 7 deletes the branch
+    apply(&mut plog, &mut key_manager, keygen("/keys/signing", false)?)?;
+    apply(
+        &mut plog,
+        &mut key_manager,
+        OpParams::Delete {
+            key: Key::try_from("/keys/")?,
+        },
+    )?;
+    let validity = KeyValidity::new(&plog)?;
+    let signing = validity.intervals("/keys/signing");
+    assert_eq!(signing.len(), 1);
+    assert_eq!((signing[0].from, signing[0].until), (6, Some(7)));
+    assert!(validity.key_at("/keys/signing", 7).is_none());
+
+    Ok(())
+}
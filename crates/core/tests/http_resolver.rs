@@ -0,0 +1,177 @@
+//! Tests for the trustless HTTP gateway resolver, against a minimal in-process gateway.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use std::collections::HashMap;
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bestsign_core::{
+    http_resolver::{HttpResolver, HttpResolverError},
+    multicid::EncodedCid,
+    provenance_log::Log,
+    resolve::{plog_blocks, resolve_plog, Resolver as _},
+    Base,
+};
+use fixtures::{generate_updated_plog, init_logger};
+
+/// A stand-in trustless gateway serving blocks from a map of request path to body.
+struct Gateway {
+    url: String,
+    blocks: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl Gateway {
+    /// Binds to a random local port and serves requests on a background thread
+    fn spawn() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let blocks: Arc<Mutex<HashMap<String, Vec<u8>>>> = Default::default();
+
+        let served = blocks.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+
+                // request line is "GET /ipfs/{cid}?format=raw HTTP/1.1", ignore the headers
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                loop {
+                    let mut header = String::new();
+                    if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" {
+                        break;
+                    }
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let response = match served.lock().unwrap().get(path) {
+                    Some(body) => {
+                        let mut response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/vnd.ipld.raw\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(body);
+                        response
+                    }
+                    None => {
+                        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_vec()
+                    }
+                };
+                let _ = stream.write_all(&response);
+            }
+        });
+
+        Self { url, blocks }
+    }
+
+    /// Serve the given body for the [Cid]
+    fn insert(&self, cid: &bestsign_core::Cid, body: Vec<u8>) {
+        let encoded = EncodedCid::new(Base::Base32Lower, cid.clone());
+        self.blocks
+            .lock()
+            .unwrap()
+            .insert(format!("/ipfs/{encoded}?format=raw"), body);
+    }
+
+    /// Serve all the blocks of the [Log]
+    fn insert_log(&self, log: &Log) {
+        for (cid, data) in plog_blocks(log) {
+            self.insert(&cid, data);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_resolve_plog_over_http() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let gateway = Gateway::spawn();
+
+    let (plog, _) = generate_updated_plog(2)?;
+    gateway.insert_log(&plog);
+
+    let resolver = HttpResolver::new([gateway.url.as_str()]).with_timeout(Duration::from_secs(5));
+
+    let resolved = resolve_plog(&plog.vlad, &plog.head, resolver).await?;
+    assert_eq!(resolved.log, plog);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_falls_back_to_next_gateway() -> Result<(), Box<dyn std::error::Error>> {
+    let empty = Gateway::spawn();
+    let full = Gateway::spawn();
+
+    let (plog, _) = generate_updated_plog(1)?;
+    full.insert_log(&plog);
+
+    let resolver = HttpResolver::new([empty.url.as_str(), full.url.as_str()]);
+
+    let resolved = resolve_plog(&plog.vlad, &plog.head, resolver).await?;
+    assert_eq!(resolved.log, plog);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_mismatched_body() -> Result<(), Box<dyn std::error::Error>> {
+    let gateway = Gateway::spawn();
+
+    let (plog, _) = generate_updated_plog(1)?;
+
+    // serve the foot entry under the head cid
+    let foot_bytes: Vec<u8> = plog.entries[&plog.foot].clone().into();
+    gateway.insert(&plog.head, foot_bytes);
+
+    let resolver = HttpResolver::new([gateway.url.as_str()]);
+
+    assert!(matches!(
+        resolver.resolve(&plog.head).await,
+        Err(HttpResolverError::InvalidBlock { .. })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_oversized_block() -> Result<(), Box<dyn std::error::Error>> {
+    let gateway = Gateway::spawn();
+
+    let (plog, _) = generate_updated_plog(0)?;
+    gateway.insert_log(&plog);
+
+    let resolver = HttpResolver::new([gateway.url.as_str()]).with_max_block_size(8);
+
+    assert!(matches!(
+        resolver.resolve(&plog.head).await,
+        Err(HttpResolverError::TooLarge { limit: 8, .. })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_block_and_no_gateways() -> Result<(), Box<dyn std::error::Error>> {
+    let gateway = Gateway::spawn();
+
+    let (plog, _) = generate_updated_plog(0)?;
+
+    let resolver = HttpResolver::new([gateway.url.as_str()]);
+    assert!(matches!(
+        resolver.resolve(&plog.head).await,
+        Err(HttpResolverError::Status { status: 404, .. })
+    ));
+
+    let resolver = HttpResolver::new(Vec::<String>::new());
+    assert!(matches!(
+        resolver.resolve(&plog.head).await,
+        Err(HttpResolverError::NoGateways)
+    ));
+
+    Ok(())
+}
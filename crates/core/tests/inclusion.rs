@@ -0,0 +1,143 @@
+//! Tests for lipmaa inclusion proofs and key certificates.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    ops::{
+        update::{OpParams, UpdateConfig},
+        update_plog,
+    },
+    provenance_log::{Key, LogValue},
+    InclusionProof, KeyCertificate, Multikey, Views as _,
+};
+use fixtures::{append_str, generate_updated_plog, init_logger, unlock_script};
+
+#[test]
+fn test_inclusion_proof() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (plog, _) = generate_updated_plog(20)?;
+    let head_seqno = plog.entries[&plog.head].seqno();
+    assert_eq!(head_seqno, 20);
+
+    for seqno in 0..=head_seqno {
+        let proof = InclusionProof::new(&plog, seqno)?;
+        let entry = proof.verify(&plog.vlad, &plog.head)?;
+        assert_eq!(entry.seqno(), seqno);
+        assert_eq!(plog.entries[&entry.cid()], *entry);
+    }
+
+    // the long hops make the path to the foot shorter than the log
+    let proof = InclusionProof::new(&plog, 0)?;
+    assert!(proof.path.len() < plog.entries.len());
+
+    // roundtrip through the compact encoding
+    let bytes: Vec<u8> = proof.clone().into();
+    let decoded = InclusionProof::try_from(bytes.as_slice())?;
+    assert_eq!(decoded, proof);
+    assert!(InclusionProof::try_from(&bytes[..bytes.len() - 1]).is_err());
+
+    // past the head
+    assert!(InclusionProof::new(&plog, head_seqno + 1).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_tampered_inclusion_proof() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(5)?;
+    let (other, _) = generate_updated_plog(5)?;
+    let proof = InclusionProof::new(&plog, 1)?;
+
+    // wrong head or vlad
+    assert!(proof.verify(&plog.vlad, &plog.foot).is_err());
+    assert!(proof.verify(&other.vlad, &plog.head).is_err());
+
+    // a gap in the path
+    let mut forged = proof.clone();
+    forged.path.remove(1);
+    assert!(forged.verify(&plog.vlad, &plog.head).is_err());
+
+    // an entry from another log
+    let mut forged = proof.clone();
+    let last = forged.path.len() - 1;
+    forged.path[last] = other.entries[&other.foot].clone();
+    assert!(forged.verify(&plog.vlad, &plog.head).is_err());
+
+    // nothing at all
+    let forged = InclusionProof { path: vec![] };
+    assert!(forged.verify(&plog.vlad, &plog.head).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_key_certificate() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(10)?;
+    append_str(&mut plog, &mut key_manager, "/other/", "value")?;
+
+    // /pubkey is set in the first entry
+    let pubkey_path = Key::try_from("/pubkey")?;
+    let cert = KeyCertificate::latest(&plog, &pubkey_path)?;
+    assert_eq!(cert.seqno(), Some(0));
+    let LogValue::Data(data) = cert.verify(&plog.vlad, &plog.head)? else {
+        panic!("Expected /pubkey to be data");
+    };
+    let pubkey: Multikey = key_manager
+        .entry_key()
+        .unwrap()
+        .conv_view()?
+        .to_public_key()?;
+    assert_eq!(Multikey::try_from(data.as_slice())?, pubkey);
+
+    // /hello/ was last set by the entry before the /other/ write
+    let hello = Key::try_from("/hello/")?;
+    let cert = KeyCertificate::latest(&plog, &hello)?;
+    assert_eq!(cert.seqno(), Some(10));
+    assert_eq!(
+        cert.verify(&plog.vlad, &plog.head)?,
+        &LogValue::Str("World 9!".to_string())
+    );
+
+    // and as of seqno 5
+    let cert = KeyCertificate::new(&plog, &hello, 5)?;
+    assert_eq!(cert.seqno(), Some(5));
+    assert_eq!(cert.value, LogValue::Str("World 4!".to_string()));
+    cert.verify(&plog.vlad, &plog.head)?;
+
+    // a forged value does not verify
+    let mut forged = cert.clone();
+    forged.value = LogValue::Str("World 5!".to_string());
+    assert!(forged.verify(&plog.vlad, &plog.head).is_err());
+
+    // unknown key
+    assert!(KeyCertificate::latest(&plog, &Key::try_from("/missing")?).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_key_certificate_after_branch_delete() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, mut key_manager) = generate_updated_plog(1)?;
+    append_str(&mut plog, &mut key_manager, "/branch/a", "a")?;
+    let config = UpdateConfig::new(unlock_script(), key_manager.entry_key().unwrap_or_default())
+        .add_op(OpParams::Delete {
+            key: Key::try_from("/branch/")?,
+        })
+        .build();
+    update_plog(&mut plog, &config, &mut key_manager)?;
+
+    // the value was set before the delete
+    let key = Key::try_from("/branch/a")?;
+    let cert = KeyCertificate::new(&plog, &key, 2)?;
+    assert_eq!(cert.value, LogValue::Str("a".to_string()));
+
+    // but deleting the branch deleted it
+    assert!(KeyCertificate::latest(&plog, &key).is_err());
+
+    // and the deleting entry certifies nothing for it
+    let mut forged = cert.clone();
+    forged.proof = InclusionProof::new(&plog, 3)?;
+    assert!(forged.verify(&plog.vlad, &plog.head).is_err());
+
+    Ok(())
+}
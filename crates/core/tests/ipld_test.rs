@@ -2,6 +2,7 @@
 mod fixtures;
 use fixtures::{RawBlock, TestKeyManager};
 
+use bestsign_core::envelope::encode_log;
 use bestsign_core::ops::config::{LockScript, UnlockScript};
 use bestsign_core::ops::create;
 use bestsign_core::ops::open::config::NewLogBuilder;
@@ -46,7 +47,7 @@ async fn test_ipld() {
     // bytes should match
     assert_eq!(head_cid_converted.to_bytes(), head_cid.clone());
 
-    let plog_bytes: Vec<u8> = plog.into();
+    let plog_bytes = encode_log(&plog);
 
     let plog_block = RawBlock(plog_bytes);
     let root_cid = plog_block.cid().unwrap();
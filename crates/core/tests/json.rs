@@ -0,0 +1,124 @@
+//! Tests for the DAG-JSON export and import of plogs.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::json::{
+    entry_from_json, entry_to_json, log_from_json, log_from_json_str, log_to_json,
+    log_to_json_string,
+};
+use fixtures::{generate_updated_plog, init_logger};
+use provenance_log::{entry, Key, LogValue, Op, Script};
+
+#[test]
+fn test_json_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let (plog, _) = generate_updated_plog(4)?;
+
+    let json = log_to_json_string(&plog)?;
+    let imported = log_from_json_str(&json)?;
+    assert_eq!(imported, plog);
+
+    // the imported log verifies the same way
+    let original: Vec<_> = plog.verify().map(|r| r.map(|(c, e, _)| (c, e))).collect();
+    let verified: Vec<_> = imported
+        .verify()
+        .map(|r| r.map(|(c, e, _)| (c, e)))
+        .collect();
+    assert_eq!(verified.len(), original.len());
+    for (a, b) in verified.into_iter().zip(original) {
+        assert_eq!(a?, b?);
+    }
+
+    // a single entry
+    let head = &plog.entries[&plog.head];
+    assert_eq!(&entry_from_json(&entry_to_json(head))?, head);
+
+    Ok(())
+}
+
+#[test]
+fn test_json_layout() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(1)?;
+    let json = log_to_json(&plog);
+
+    // CIDs are DAG-JSON links
+    assert!(json["head"]["/"].is_string());
+    let entries = json["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["seqno"], 0);
+    assert!(entries[0]["prev"].is_null());
+    assert_eq!(entries[1]["prev"], entries[0]["cid"]);
+
+    // scripts are text
+    assert!(entries[1]["unlock"]["code"]
+        .as_str()
+        .unwrap()
+        .contains("push(\"/entry/\")"));
+
+    // string values are plain, bytes are multibase
+    let ops = entries[1]["ops"].as_array().unwrap();
+    assert!(ops
+        .iter()
+        .any(|op| op["path"] == "/hello/" && op["str"] == "World 0!"));
+    assert!(entries[1]["proof"].as_str().unwrap().starts_with('m'));
+
+    Ok(())
+}
+
+#[test]
+fn test_json_op_values() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(0)?;
+    let ops = vec![
+        Op::Noop(Key::try_from("/noop")?),
+        Op::Delete(Key::try_from("/delete")?),
+        Op::Update(Key::try_from("/str")?, LogValue::Str("hello".to_string())),
+        Op::Update(Key::try_from("/data")?, LogValue::Data(vec![1, 2, 3])),
+        Op::Update(Key::try_from("/nil")?, LogValue::Nil),
+    ];
+
+    let mut builder = entry::Builder::default()
+        .with_vlad(&plog.vlad)
+        .with_seqno(1)
+        .with_prev(&plog.head)
+        .with_unlock(&Script::Code(
+            Key::default(),
+            "push(\"/entry/\")".to_string(),
+        ));
+    for op in &ops {
+        builder = builder.add_op(op);
+    }
+    let entry = builder.try_build(|_| Ok(vec![4, 5, 6]))?;
+
+    // every value survives the roundtrip
+    let json = entry_to_json(&entry);
+    let imported = entry_from_json(&json)?;
+    assert_eq!(imported, entry);
+    assert_eq!(imported.ops().cloned().collect::<Vec<_>>(), ops);
+
+    let values = json["ops"].as_array().unwrap();
+    assert_eq!(values[2]["str"], "hello");
+    assert!(values[3]["data"].as_str().unwrap().starts_with('m'));
+    assert!(values[4]["nil"].is_null() && values[4].get("nil").is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_json_tampered() -> Result<(), Box<dyn std::error::Error>> {
+    let (plog, _) = generate_updated_plog(1)?;
+    let mut json = log_to_json(&plog);
+
+    // changing a value no longer rebuilds the signed entry
+    for op in json["entries"][1]["ops"].as_array_mut().unwrap() {
+        if op["path"] == "/hello/" {
+            op["str"] = "Mallory".into();
+        }
+    }
+    assert!(log_from_json(&json).is_err());
+
+    // garbage
+    assert!(log_from_json_str("{}").is_err());
+    assert!(log_from_json_str("not json").is_err());
+
+    Ok(())
+}
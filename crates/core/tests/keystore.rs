@@ -0,0 +1,189 @@
+//! Tests for the encrypted keystore.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::error::{CryptoError, KeystoreError};
+use bestsign_core::ops::config::defaults::{DEFAULT_ENTRYKEY, DEFAULT_PUBKEY, DEFAULT_VLAD_KEY};
+use bestsign_core::ops::config::{LockScript, UnlockScript};
+use bestsign_core::ops::open::config::NewLogBuilder;
+use bestsign_core::ops::update::{OpParams, UpdateConfig};
+use bestsign_core::ops::{create, update_plog, CryptoManager};
+use bestsign_core::provenance_log::Key;
+use bestsign_core::{Codec, EncodedMultikey, Error, Keystore, Views};
+use fixtures::{init_logger, lock_script, unlock_script};
+
+fn temp_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("bestsign-keystore-{}", rand::random::<u64>()))
+}
+
+#[test]
+fn test_keystore_create_and_update() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let path = temp_path();
+    let mut keystore = Keystore::create(&path, b"correct horse")?;
+    keystore.select("alice");
+
+    let config =
+        NewLogBuilder::new(LockScript(lock_script()), UnlockScript(unlock_script())).build();
+    let mut plog = create(&config, &mut keystore)?;
+    keystore.save()?;
+
+    let slots = keystore.list();
+    assert!(slots.contains(&("alice".to_string(), DEFAULT_PUBKEY.to_string())));
+
+    // the one-time keys are not kept
+    assert!(!keystore.contains("alice", DEFAULT_ENTRYKEY));
+    assert!(!keystore.contains("alice", DEFAULT_VLAD_KEY));
+
+    // reopen, unlock and sign an update with the stored /pubkey
+    let mut keystore = Keystore::open(&path)?;
+    assert!(keystore.is_locked());
+    assert_eq!(keystore.list(), slots);
+    keystore.unlock(b"correct horse")?;
+    keystore.select("alice");
+
+    let pubkey = keystore.get("alice", DEFAULT_PUBKEY)?;
+    let update_cfg = UpdateConfig::new(unlock_script(), pubkey)
+        .add_op(OpParams::UseStr {
+            key: Key::try_from("/hello/")?,
+            s: "World!".to_string(),
+        })
+        .build();
+    update_plog(&mut plog, &update_cfg, &mut keystore)?;
+    assert!(plog.verify().all(|r| r.is_ok()));
+
+    // only the owner can read the secret keys
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        keystore.save()?;
+        let mode = std::fs::metadata(&path)?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_keystore_lock_export_delete() -> Result<(), Box<dyn std::error::Error>> {
+    let path = temp_path();
+    let mut keystore = Keystore::create(&path, b"password")?;
+    keystore.select("bob");
+    let config =
+        NewLogBuilder::new(LockScript(lock_script()), UnlockScript(unlock_script())).build();
+    create(&config, &mut keystore)?;
+    keystore.save()?;
+
+    let pubkey = keystore.get("bob", DEFAULT_PUBKEY)?;
+
+    // the export is the multibase secret key
+    let exported = keystore.export("bob", DEFAULT_PUBKEY)?;
+    assert_eq!(
+        EncodedMultikey::try_from(exported.as_str())?.to_inner(),
+        pubkey
+    );
+
+    // locked keystores give nothing out
+    keystore.lock();
+    assert!(matches!(
+        keystore.get("bob", DEFAULT_PUBKEY),
+        Err(Error::Crypto(CryptoError::KeystoreLocked))
+    ));
+    let err = create(&config, &mut keystore).unwrap_err();
+    assert!(matches!(err, Error::KeyGeneration { .. }));
+    assert_eq!(err.crypto(), Some(&CryptoError::KeystoreLocked));
+    assert!(matches!(
+        keystore.unlock(b"wrong"),
+        Err(Error::Keystore(KeystoreError::WrongPassword))
+    ));
+    keystore.unlock(b"password")?;
+
+    // missing keys
+    assert!(matches!(
+        keystore.get("carol", DEFAULT_PUBKEY),
+        Err(Error::Crypto(CryptoError::KeyNotFound(_)))
+    ));
+
+    // codecs that are not keys
+    let err = keystore
+        .get_mk(&Key::try_from(DEFAULT_PUBKEY)?, Codec::Sha2256, 1, 1)
+        .unwrap_err();
+    assert_eq!(
+        err.crypto(),
+        Some(&CryptoError::UnsupportedCodec(Codec::Sha2256.to_string()))
+    );
+
+    // deleting is persisted
+    assert!(keystore.delete("bob", DEFAULT_PUBKEY)?);
+    assert!(!keystore.delete("bob", DEFAULT_PUBKEY)?);
+    let reopened = Keystore::open(&path)?;
+    assert!(!reopened.contains("bob", DEFAULT_PUBKEY));
+    assert!(reopened.list().is_empty());
+
+    // an existing file is never overwritten
+    assert!(Keystore::create(&path, b"password").is_err());
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_keystore_insert_and_tamper() -> Result<(), Box<dyn std::error::Error>> {
+    let path = temp_path();
+    let mut keystore = Keystore::create(&path, b"password")?;
+
+    let mut rng = rand::rngs::OsRng;
+    let mk = bestsign_core::mk::Builder::new_from_random_bytes(
+        bestsign_core::Codec::Ed25519Priv,
+        &mut rng,
+    )?
+    .try_build()?;
+    keystore.insert("dave", "/pubkey", &mk)?;
+    assert_eq!(
+        keystore
+            .get("dave", "/pubkey")?
+            .conv_view()?
+            .to_public_key()?,
+        mk.conv_view()?.to_public_key()?
+    );
+
+    // removing a label is only persisted by saving
+    keystore.insert("erin", "/pubkey", &mk)?;
+    assert_eq!(keystore.remove_label("erin")?, 1);
+    assert!(!keystore.contains("erin", "/pubkey"));
+    assert!(Keystore::open(&path)?.contains("erin", "/pubkey"));
+    keystore.save()?;
+    assert!(!Keystore::open(&path)?.contains("erin", "/pubkey"));
+    assert!(Keystore::open(&path)?.contains("dave", "/pubkey"));
+
+    // the header has the format version and the Argon2id parameters, which the
+    // key is derived with
+    let mut bytes = std::fs::read(&path)?;
+    assert_eq!(&bytes[..4], bestsign_core::keystore::MAGIC);
+    assert_eq!(&bytes[4..10], &[2, 0x80, 0x98, 0x01, 2, 1]);
+    bytes[8] = 3;
+    let other = temp_path();
+    std::fs::write(&other, &bytes)?;
+    assert!(matches!(
+        Keystore::open(&other)?.unlock(b"password"),
+        Err(Error::Keystore(KeystoreError::WrongPassword))
+    ));
+    std::fs::remove_file(other)?;
+
+    // flipping a byte of the file breaks it
+    let mut bytes = std::fs::read(&path)?;
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&path, &bytes)?;
+    let mut keystore = Keystore::open(&path)?;
+    keystore.unlock(b"password")?;
+    assert!(keystore.get("dave", "/pubkey").is_err());
+
+    // garbage is not a keystore
+    std::fs::write(&path, b"not a keystore")?;
+    assert!(Keystore::open(&path).is_err());
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
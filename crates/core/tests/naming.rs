@@ -0,0 +1,100 @@
+//! Tests for resolving a plog by its Vlad through a head store.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    fs_resolver::FsBlockstore,
+    resolve::{
+        naming::{FsHeadStore, HeadStoreError, MemoryHeadStore},
+        resolve_by_vlad, HeadResolver as _,
+    },
+};
+use fixtures::{generate_updated_plog, init_logger};
+
+#[tokio::test]
+async fn test_resolve_by_vlad_memory() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let dir = std::env::temp_dir().join(format!("bestsign-naming-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(2)?;
+    blocks.put_log(&plog)?;
+
+    let heads = MemoryHeadStore::new();
+
+    // unknown vlad
+    assert!(resolve_by_vlad(&plog.vlad, &heads, blocks.clone())
+        .await
+        .is_err());
+
+    heads.set_head(&plog.vlad, &plog.head);
+
+    let resolved = resolve_by_vlad(&plog.vlad, &heads, blocks.clone()).await?;
+    assert_eq!(resolved.log, plog);
+
+    // pointing at an older head resolves the shorter log
+    let prev = plog.entries[&plog.head].prev();
+    heads.set_head(&plog.vlad, &prev);
+
+    let resolved = resolve_by_vlad(&plog.vlad, &heads, blocks).await?;
+    assert_eq!(resolved.log.head, prev);
+    assert_eq!(resolved.log.entries.len(), plog.entries.len() - 1);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resolve_by_vlad_fs() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("bestsign-naming-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(dir.join("blocks"))?;
+    let heads = FsHeadStore::open(dir.join("heads"))?;
+
+    let (plog, _) = generate_updated_plog(1)?;
+    blocks.put_log(&plog)?;
+
+    assert!(matches!(
+        heads.resolve_head(&plog.vlad).await,
+        Err(HeadStoreError::NotFound(_))
+    ));
+
+    heads.set_head(&plog.vlad, &plog.foot)?;
+    heads.set_head(&plog.vlad, &plog.head)?;
+    assert_eq!(heads.get_head(&plog.vlad)?, plog.head);
+
+    let resolved = resolve_by_vlad(&plog.vlad, &heads, blocks).await?;
+    assert_eq!(resolved.log, plog);
+
+    heads.remove(&plog.vlad)?;
+    assert!(heads.get_head(&plog.vlad).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_set_head() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("bestsign-naming-{}", rand::random::<u64>()));
+    let heads = FsHeadStore::open(&dir)?;
+    let (plog, _) = generate_updated_plog(1)?;
+
+    // writers racing on the same Vlad never share a temporary file
+    std::thread::scope(|scope| {
+        for i in 0..8 {
+            let (heads, plog) = (&heads, &plog);
+            scope.spawn(move || {
+                let head = if i % 2 == 0 { &plog.head } else { &plog.foot };
+                for _ in 0..20 {
+                    heads.set_head(&plog.vlad, head).unwrap();
+                }
+            });
+        }
+    });
+
+    let head = heads.get_head(&plog.vlad)?;
+    assert!(head == plog.head || head == plog.foot);
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
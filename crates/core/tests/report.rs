@@ -0,0 +1,73 @@
+//! Tests for per-entry verification reports.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use bestsign_core::{
+    fs_resolver::FsBlockstore,
+    provenance_log::{Key, Script},
+    report::EntryStatus,
+    resolve::{resolve_plog, resolve_plog_report},
+    VerificationReport,
+};
+use fixtures::{generate_updated_plog, init_logger};
+
+#[tokio::test]
+async fn test_report_valid_log() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let dir = std::env::temp_dir().join(format!("bestsign-report-{}", rand::random::<u64>()));
+    let blocks = FsBlockstore::open(&dir)?;
+
+    let (plog, _) = generate_updated_plog(3)?;
+    blocks.put_log(&plog)?;
+
+    let report = VerificationReport::new(&plog);
+    assert!(report.is_valid());
+    assert!(report.first_failure().is_none());
+    assert_eq!(report.head, plog.head);
+    assert_eq!(report.entries.len(), plog.entries.len());
+    for (i, entry) in report.entries.iter().enumerate() {
+        assert_eq!(entry.seqno, i as u64);
+        assert_eq!(plog.entries[&entry.cid].seqno(), entry.seqno);
+        assert!(entry.count.is_some());
+        assert!(entry.error.is_none());
+        assert!(entry.candidate_lock.is_some());
+    }
+
+    // the counts match resolving, and so does the report from resolving
+    let resolved = resolve_plog(&plog.vlad, &plog.head, blocks.clone()).await?;
+    assert_eq!(report.total_count(), resolved.total_count());
+
+    let (log, resolved_report) = resolve_plog_report(&plog.vlad, &plog.head, blocks).await?;
+    assert_eq!(log, plog);
+    assert_eq!(resolved_report, report);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_report_broken_log() -> Result<(), Box<dyn std::error::Error>> {
+    let (mut plog, _) = generate_updated_plog(3)?;
+
+    // a first lock nothing can satisfy breaks the first entry
+    plog.first_lock = Script::Code(Key::default(), r#"check_preimage("/nothing")"#.to_string());
+
+    let report = VerificationReport::new(&plog);
+    assert!(!report.is_valid());
+
+    let failure = report.first_failure().expect("a failed entry");
+    assert_eq!(failure.seqno, 0);
+    assert_eq!(failure.status, EntryStatus::Failed);
+    assert!(failure.error.is_some());
+    assert_eq!(failure.candidate_lock, None);
+
+    // everything after it is unverified
+    assert!(report.entries[1..]
+        .iter()
+        .all(|entry| entry.status == EntryStatus::Unverified
+            && entry.count.is_none()
+            && entry.candidate_lock.is_none()));
+    assert_eq!(report.total_count(), 0);
+
+    Ok(())
+}
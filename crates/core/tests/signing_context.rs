@@ -0,0 +1,129 @@
+//! The signing context passed to key managers, and user rejections.
+#[path = "./fixtures.rs"]
+mod fixtures;
+
+use std::cell::RefCell;
+
+use bestsign_core::error::CryptoError;
+use bestsign_core::ops::config::defaults::{DEFAULT_ENTRYKEY, DEFAULT_PUBKEY, DEFAULT_VLAD_KEY};
+use bestsign_core::ops::config::{LockScript, UnlockScript};
+use bestsign_core::ops::open::config::NewLogBuilder;
+use bestsign_core::ops::update::{OpParams, UpdateConfig};
+use bestsign_core::ops::{create, update_plog, CryptoManager, SigningContext, SigningPurpose};
+use bestsign_core::provenance_log::Key;
+use bestsign_core::utils::OpKind;
+use bestsign_core::{Codec, Error, Multikey, Multisig};
+use fixtures::{init_logger, lock_script, unlock_script, TestKeyManager};
+
+/// Records every signing context, and rejects the purposes it is told to
+#[derive(Default)]
+struct ConfirmingKeyManager {
+    inner: TestKeyManager,
+    contexts: RefCell<Vec<SigningContext>>,
+    reject_updates: bool,
+}
+
+impl CryptoManager for ConfirmingKeyManager {
+    fn get_mk(
+        &mut self,
+        key: &Key,
+        codec: Codec,
+        threshold: usize,
+        limit: usize,
+    ) -> Result<Multikey, Error> {
+        self.inner.get_mk(key, codec, threshold, limit)
+    }
+
+    fn prove(&self, _mk: &Multikey, _data: &[u8]) -> Result<Multisig, Error> {
+        panic!("signing without a context")
+    }
+
+    fn prove_with_context(
+        &self,
+        mk: &Multikey,
+        data: &[u8],
+        context: &SigningContext,
+    ) -> Result<Multisig, Error> {
+        self.contexts.borrow_mut().push(context.clone());
+        if self.reject_updates && context.purpose == SigningPurpose::Update {
+            return Err(CryptoError::UserRejected("not today".to_string()).into());
+        }
+        self.inner.prove(mk, data)
+    }
+}
+
+fn new_config() -> bestsign_core::ops::open::config::Config {
+    NewLogBuilder::new(LockScript(lock_script()), UnlockScript(unlock_script())).build()
+}
+
+#[test]
+fn test_create_and_update_contexts() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let mut key_manager = ConfirmingKeyManager::default();
+    let mut plog = create(&new_config(), &mut key_manager)?;
+
+    {
+        let contexts = key_manager.contexts.borrow();
+        assert_eq!(contexts.len(), 2);
+
+        assert!(matches!(contexts[0].purpose, SigningPurpose::Vlad { .. }));
+        assert_eq!(contexts[0].vlad, None);
+        assert_eq!(contexts[0].key_path, Some(Key::try_from(DEFAULT_VLAD_KEY)?));
+
+        assert_eq!(contexts[1].purpose, SigningPurpose::FirstEntry);
+        assert_eq!(contexts[1].vlad, Some(plog.vlad.clone()));
+        assert_eq!(contexts[1].seqno, Some(0));
+        assert_eq!(contexts[1].key_path, Some(Key::try_from(DEFAULT_ENTRYKEY)?));
+        assert!(contexts[1]
+            .ops
+            .iter()
+            .any(|op| op.kind == OpKind::Update && op.key_path.as_str() == DEFAULT_PUBKEY));
+    }
+
+    let update_cfg = UpdateConfig::new(unlock_script(), key_manager.inner.entry_key().unwrap())
+        .add_op(OpParams::UseStr {
+            key: Key::try_from("/hello/")?,
+            s: "World!".to_string(),
+        })
+        .build();
+    update_plog(&mut plog, &update_cfg, &mut key_manager)?;
+
+    let contexts = key_manager.contexts.borrow();
+    let update = contexts.last().unwrap();
+    assert_eq!(update.purpose, SigningPurpose::Update);
+    assert_eq!(update.vlad, Some(plog.vlad.clone()));
+    assert_eq!(update.seqno, Some(1));
+    assert_eq!(update.key_path, Some(Key::try_from(DEFAULT_PUBKEY)?));
+    assert!(update
+        .ops
+        .iter()
+        .any(|op| op.kind == OpKind::Update && op.key_path.as_str() == "/hello/"));
+
+    Ok(())
+}
+
+#[test]
+fn test_user_rejection() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+    let mut key_manager = ConfirmingKeyManager {
+        reject_updates: true,
+        ..Default::default()
+    };
+    let mut plog = create(&new_config(), &mut key_manager)?;
+    let head = plog.head.clone();
+
+    let update_cfg = UpdateConfig::new(unlock_script(), key_manager.inner.entry_key().unwrap())
+        .add_op(OpParams::Noop {
+            key: Key::try_from("/hello/")?,
+        })
+        .build();
+    let err = update_plog(&mut plog, &update_cfg, &mut key_manager).unwrap_err();
+    assert!(matches!(err, Error::EntrySigning(_)));
+    assert_eq!(
+        err.crypto(),
+        Some(&CryptoError::UserRejected("not today".to_string()))
+    );
+    assert_eq!(plog.head, head);
+
+    Ok(())
+}
@@ -3,17 +3,34 @@
 //! Pass in [Credentials] such as `{"username":"username","password":"password","encrypted_seed":null}` to create a wallet,
 //!
 //! Or use a seed {"username":"username","password":"password","encrypted_seed":[46,236,62,136,201,70,17,15,212,216,99,70,0,242,150,190,15,58,71,131,148,196,18,158,104,110,121,170,241,22,47,63,211,192,118,233,214,196,223,34]}
+//!
+//! Keys other than the ephemeral `/entrykey` and `/vlad/key` are derived from the seed
+//! with [bestsign_core::derive], by key-path, codec and the next rotation of the
+//! key-path. Rotations start at 0 and every derived key moves the key-path on to the
+//! next one, as [bestsign_core::derive::SeededKeyManager] does.
+//!
+//! To carry on with an existing plog, pass each key stored in it to `recoverKey`,
+//! which finds its secret key and sets the next rotation of the key-path. Plogs made
+//! before the derivation scheme used `mk::Builder::new_from_seed(codec, seed)` for
+//! every key-path; `recoverKey` falls back to that legacy key when no rotation of the
+//! scheme matches. `setRotation` sets the next rotation by hand.
 
 use std::collections::HashMap;
 
+use bestsign_core::derive::{derive_key, recover_key};
+use bestsign_core::error::CryptoError;
 use bestsign_core::ops::config::defaults::{DEFAULT_ENTRYKEY, DEFAULT_VLAD_KEY};
-use bestsign_core::provenance_log::Key;
+use bestsign_core::provenance_log::{multiutil::CodecInfo, Key};
+use bestsign_core::utils::secret_codec;
 use bestsign_core::{Codec, Multikey};
 use multikey::{mk, EncodedMultikey, Views as _};
 use seed_keeper_core::credentials::{Credentials, Wallet};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+/// How many rotations of a key-path `recoverKey` tries
+const MAX_RECOVERY_ROTATION: u64 = 256;
+
 /// The arguments for the get_key callback
 #[derive(Serialize, Deserialize)]
 pub struct KeyArgs {
@@ -38,6 +55,42 @@ pub struct WasmWallet {
 
     /// A map of encoded public keys to their corresponding Multikey and Key
     keys: HashMap<String, (Multikey, Key)>,
+
+    /// The next rotation of each key-path, 0 if not set
+    rotations: HashMap<String, u64>,
+}
+
+/// A [CryptoError] as thrown to the core bindings, which read its `code`
+#[derive(Serialize)]
+struct CryptoErrorArgs {
+    code: &'static str,
+    reason: String,
+    message: String,
+}
+
+/// Helper fn which turns the [CryptoError] into the object the core bindings expect
+fn crypto_error(e: CryptoError) -> JsValue {
+    let args = CryptoErrorArgs {
+        code: e.code(),
+        reason: match &e {
+            CryptoError::KeyNotFound(reason)
+            | CryptoError::UserRejected(reason)
+            | CryptoError::UnsupportedCodec(reason)
+            | CryptoError::SignerUnavailable(reason) => reason.clone(),
+            _ => String::new(),
+        },
+        message: e.to_string(),
+    };
+    serde_wasm_bindgen::to_value(&args).unwrap_or_else(|_| JsValue::from_str(&e.to_string()))
+}
+
+/// Helper fn which throws a failed key build as a [CryptoError] when it is about the
+/// codec, and as its message otherwise
+fn key_error(e: impl Into<bestsign_core::Error>, codec: Codec) -> JsValue {
+    match e.into().or_unsupported_codec(codec) {
+        bestsign_core::Error::Crypto(e) => crypto_error(e),
+        e => into_js_val(e),
+    }
 }
 
 /// Helper fn which does `|e| JsValue::from_str(&e.to_string())`
@@ -63,8 +116,71 @@ impl WasmWallet {
             serde_wasm_bindgen::from_value(credentials).map_err(into_js_val)?;
         let wallet = Wallet::new(credentials).map_err(into_js_val)?;
         let keys = HashMap::new();
-        Ok(WasmWallet { wallet, keys })
+        let rotations = HashMap::new();
+        Ok(WasmWallet {
+            wallet,
+            keys,
+            rotations,
+        })
+    }
+
+    /// Returns the rotation the next key for the key-path is derived with
+    pub fn rotation(&self, key: &str) -> u64 {
+        self.rotations.get(key).copied().unwrap_or_default()
+    }
+
+    /// Sets the rotation of the key-path, the next key for it is derived with it
+    #[wasm_bindgen(js_name = setRotation)]
+    pub fn set_rotation(&mut self, key: &str, rotation: u64) {
+        self.rotations.insert(key.to_string(), rotation);
+    }
+
+    /// Finds the secret key of the encoded public key stored at the key-path of a
+    /// plog, so it can sign the next entry
+    ///
+    /// The rotations of the derivation scheme are tried first, and the next rotation
+    /// of the key-path is set past the one found. Otherwise the legacy key,
+    /// `mk::Builder::new_from_seed(codec, seed)` for any key-path, is tried.
+    #[wasm_bindgen(js_name = recoverKey)]
+    pub fn recover_key(&mut self, key: &str, public_key: &str) -> Result<JsValue, JsValue> {
+        let key_path = Key::try_from(key).map_err(into_js_val)?;
+        let public_key = EncodedMultikey::try_from(public_key)
+            .map_err(into_js_val)?
+            .to_inner();
+        let seed = self.wallet.seed();
+
+        let mk = match recover_key(seed, &key_path, &public_key, MAX_RECOVERY_ROTATION)
+            .map_err(into_js_val)?
+        {
+            Some((rotation, mk)) => {
+                self.rotations.insert(key.to_string(), rotation + 1);
+                mk
+            }
+            None => {
+                let codec = secret_codec(public_key.codec());
+                let legacy = mk::Builder::new_from_seed(codec, seed)
+                    .map_err(into_js_val)?
+                    .try_build()
+                    .map_err(into_js_val)?;
+                let legacy_public = legacy
+                    .conv_view()
+                    .map_err(into_js_val)?
+                    .to_public_key()
+                    .map_err(into_js_val)?;
+                if legacy_public != public_key {
+                    return Err(crypto_error(CryptoError::KeyNotFound(key.to_string())));
+                }
+                legacy
+            }
+        };
+
+        self.keys.insert(
+            EncodedMultikey::from(public_key).to_string(),
+            (mk.clone(), key_path),
+        );
+        serde_wasm_bindgen::to_value(&mk).map_err(into_js_val)
     }
+
     /// Returns the Encrypted Seed of the Wallet
     #[wasm_bindgen(js_name = encryptedSeed)]
     pub fn encrypted_seed(&self) -> Result<JsValue, JsValue> {
@@ -84,7 +200,8 @@ impl WasmWallet {
             limit: _,
         } = serde_wasm_bindgen::from_value(args).map_err(into_js_val)?;
 
-        let codec = Codec::try_from(codec.as_str()).map_err(into_js_val)?;
+        let codec = Codec::try_from(codec.as_str())
+            .map_err(|_| crypto_error(CryptoError::UnsupportedCodec(codec.clone())))?;
 
         // if key is DEFAULT_ENTRYKEY or DEFAULT_VLAD_KEY, generate random key.
         // Otherwise, use the key from seed.
@@ -92,20 +209,20 @@ impl WasmWallet {
             DEFAULT_ENTRYKEY | DEFAULT_VLAD_KEY => {
                 let mut rng = rand::thread_rng();
                 let mk = mk::Builder::new_from_random_bytes(codec, &mut rng)
-                    .map_err(into_js_val)?
-                    .try_build()
-                    .map_err(into_js_val)?;
+                    .and_then(|builder| builder.try_build())
+                    .map_err(|e| key_error(e, codec))?;
 
                 Ok::<Multikey, JsValue>(mk)
             }
             _ => {
-                // get seed from wallet.seed
+                // derive from wallet.seed, by key-path, codec and rotation
                 let seed = self.wallet.seed();
+                let key_path = Key::try_from(key.as_str()).map_err(into_js_val)?;
 
-                let mk = mk::Builder::new_from_seed(codec, seed)
-                    .map_err(into_js_val)?
-                    .try_build()
-                    .map_err(into_js_val)?;
+                let rotation = self.rotation(&key);
+                let mk = derive_key(seed, &key_path, codec, rotation)
+                    .map_err(|e| key_error(e, codec))?;
+                self.rotations.insert(key.clone(), rotation + 1);
 
                 Ok(mk)
             }
@@ -154,7 +271,7 @@ impl WasmWallet {
         let (mk, key) = self
             .keys
             .get(&epk.to_string())
-            .ok_or(JsError::new("Key not found."))?;
+            .ok_or_else(|| crypto_error(CryptoError::KeyNotFound(epk.to_string())))?;
 
         //tracing::info!("key: {:?}", mk);
 
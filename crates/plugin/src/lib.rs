@@ -14,7 +14,12 @@ use bindings::{
 //use chrono::{DateTime, Local, TimeZone};
 
 /// The provenance log.
-use bestsign_core::{provenance_log::Log, utils, Base, EncodedVlad, Vlad};
+use bestsign_core::{
+    envelope::{decode_log, encode_log},
+    fs_resolver::FsBlockstore,
+    provenance_log::Log,
+    utils, Base, EncodedVlad, Vlad,
+};
 
 use getrandom::register_custom_getrandom;
 
@@ -28,6 +33,9 @@ pub fn imported_random(dest: &mut [u8]) -> Result<(), getrandom::Error> {
 
 register_custom_getrandom!(imported_random);
 
+/// The directory the content addressed plog blocks are stored in
+const BLOCKS_DIR: &str = "blocks";
+
 struct Component;
 
 impl Guest for Component {
@@ -55,8 +63,8 @@ impl Guest for Component {
     /// Respond to a request with the given bytes
     fn handle_request(data: Vec<u8>) -> Result<Vec<u8>, Error> {
         // Simple check to see what kind of data we are dealing with
-        if let Ok(log) = Log::try_from(data.as_slice()) {
-            return log_handler(&log, &data);
+        if let Ok(log) = decode_log(&data) {
+            return log_handler(&log);
         }
 
         if let Ok(vlad) = Vlad::try_from(data.as_slice()) {
@@ -117,7 +125,7 @@ fn vlad_handler(vlad: Vlad) -> Result<Vec<u8>, Error> {
 //
 // So if we provide the Vlad on the DHT, we should use our blockstore
 // to recurviesly put_keyed the Log CIDs, and the Entrie CIDs
-fn log_handler(log: &Log, data: &[u8]) -> Result<Vec<u8>, Error> {
+fn log_handler(log: &Log) -> Result<Vec<u8>, Error> {
     // TODO: Ensure the encoding is the same as decoding Base
     let display = utils::get_display_data(log).map_err(|e| Error::HandlerError(e.to_string()))?;
 
@@ -137,15 +145,20 @@ fn log_handler(log: &Log, data: &[u8]) -> Result<Vec<u8>, Error> {
             let mut file =
                 File::create(&vlad.encoded).map_err(|e| Error::IoError(e.to_string()))?;
 
-            // write the binary data
-            file.write_all(data)
+            // write the binary data, always in the current envelope format
+            file.write_all(&encode_log(log))
                 .map_err(|e| Error::IoError(e.to_string()))?;
 
             println!("Vlad is verified and saved to disk");
 
+            // also store the individual blocks, so the plog can be resolved by head Cid
+            if let Err(e) = FsBlockstore::open(BLOCKS_DIR).and_then(|store| store.put_log(log)) {
+                logging::log(&format!("Failed to store plog blocks: {}", e));
+            }
+
             // start providing on the DHT
             // TODO: Use the Blake3 hash instead of the bytes
-            start_providing(&vlad.bytes);
+            start_providing(&Vec::<u8>::from(vlad.value.clone()));
 
             // return 1 for true
             return Ok(vec![1]);
@@ -6,7 +6,8 @@ use std::{
 
 use bestsign_core::{
     provenance_log::multicid::{self, vlad, Cid},
-    resolve::{resolve_plog, Resolver},
+    resolve::{choose_head, ForkChoicePolicy, HeadResolver, Resolver},
+    EquivocationProof,
 };
 use futures::StreamExt;
 use peerpiper::{
@@ -20,6 +21,9 @@ use tokio::sync::Mutex;
 // Whether the web server has started or not.
 static WEB_SERVER_STARTED: AtomicBool = AtomicBool::new(false);
 
+/// The most equivocation proofs kept, the oldest are dropped first
+const MAX_EQUIVOCATIONS: usize = 1024;
+
 /// Use PeerPiper to create a SuperPeer.
 ///
 /// Since Resolve uses PeerPiper Get, the data will be pulled into the
@@ -27,9 +31,17 @@ static WEB_SERVER_STARTED: AtomicBool = AtomicBool::new(false);
 #[derive(Clone, Default)]
 pub struct SuperPeer {
     peerpiper: Arc<Mutex<Option<PeerPiper>>>,
+    /// Equivocation proofs collected from forks seen in put record requests, one per
+    /// Vlad and at most [MAX_EQUIVOCATIONS]
+    equivocations: Arc<Mutex<Vec<EquivocationProof>>>,
 }
 
 impl SuperPeer {
+    /// The equivocation proofs seen so far
+    pub async fn equivocations(&self) -> Vec<EquivocationProof> {
+        self.equivocations.lock().await.clone()
+    }
+
     pub async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let blockstore = NativeBlockstoreBuilder::default().open().await.unwrap();
 
@@ -72,12 +84,35 @@ impl SuperPeer {
                 let vlad = vlad::Vlad::try_from(record.key.to_vec().as_slice())?;
                 let head = Cid::try_from(record.value.as_slice())?;
 
-                let _resolved_plog = resolve_plog(&vlad, &head, self.clone()).await?;
+                // Compare the check counts of the resolved Plog to any existing Plog
+                // in DHT + Blockstore, and keep the log with the lowest count.
+                // The existing head goes first, so it is kept when the two are equal.
+                let mut heads = vec![head.clone()];
+                if let Ok(existing) = self.resolve_head(&vlad).await {
+                    if existing != head {
+                        heads.insert(0, existing);
+                    }
+                }
+
+                let choice =
+                    choose_head(&vlad, &heads, self.clone(), ForkChoicePolicy::LowestCost).await?;
+
+                // Two different entries signed for the same seqno is equivocation, keep the
+                // proof so it can be shared with others.
+                let proofs = EquivocationProof::from_fork_choice(&choice);
+                for proof in &proofs {
+                    tracing::warn!(
+                        seqno = proof.seqno(),
+                        ancestor = ?proof.ancestor,
+                        "Equivocation detected"
+                    );
+                }
+                self.keep_equivocations(proofs).await;
 
-                // TODO: Compare the check counts of the Resolvedlog to any existing Plog
-                // in DHT + Blockstore, keep the log with the lowest count.
-                // For now, since all verified Plogs are valid, we'll start with
-                // keeping that last written / published one.
+                if choice.head != head {
+                    tracing::info!("Keeping existing head, the new head lost the fork choice");
+                    return Ok(());
+                }
 
                 // If we made it this far, it means we have a valid Plog and we should Put the
                 // Record.
@@ -85,18 +120,39 @@ impl SuperPeer {
                     key: vlad.into(),
                     value: head.into(),
                 };
-                self.peerpiper
-                    .lock()
-                    .await
-                    .as_ref()
-                    .unwrap()
-                    .order(put_record)
-                    .await?;
+                order(&self.peerpiper, put_record).await?;
             }
             _ => {}
         }
         Ok(())
     }
+
+    /// Keeps the first proof seen for each Vlad, dropping the oldest proofs once
+    /// there are more than [MAX_EQUIVOCATIONS]
+    async fn keep_equivocations(&self, proofs: Vec<EquivocationProof>) {
+        let mut equivocations = self.equivocations.lock().await;
+        for proof in proofs {
+            if equivocations.iter().any(|kept| kept.vlad == proof.vlad) {
+                continue;
+            }
+            if equivocations.len() == MAX_EQUIVOCATIONS {
+                equivocations.remove(0);
+            }
+            equivocations.push(proof);
+        }
+    }
+}
+
+/// Orders the command from the connected [PeerPiper], failing if there is none yet
+async fn order(
+    peerpiper: &Mutex<Option<PeerPiper>>,
+    command: AllCommands,
+) -> Result<ReturnValues, TestError> {
+    let guard = peerpiper.lock().await;
+    let connected = guard
+        .as_ref()
+        .ok_or(TestError::PerrPiper(peerpiper::Error::NotConnected))?;
+    Ok(connected.order(command).await?)
 }
 
 impl Resolver for SuperPeer {
@@ -111,14 +167,7 @@ impl Resolver for SuperPeer {
         });
         let peerpiper = self.peerpiper.clone();
         Box::pin(async move {
-            let ReturnValues::Data(data) = peerpiper
-                .lock()
-                .await
-                .as_ref()
-                .unwrap()
-                .order(command)
-                .await?
-            else {
+            let ReturnValues::Data(data) = order(&peerpiper, command).await? else {
                 return Err(TestError::PerrPiper(peerpiper::Error::NotConnected));
             };
             Ok(data)
@@ -126,8 +175,31 @@ impl Resolver for SuperPeer {
     }
 }
 
+/// Resolves the head of a plog from the `vlad -> head` DHT record
+impl HeadResolver for SuperPeer {
+    type Error = TestError;
+
+    fn resolve_head(
+        &self,
+        vlad: &multicid::Vlad,
+    ) -> Pin<Box<dyn Future<Output = Result<Cid, Self::Error>> + Send>> {
+        let command = AllCommands::GetRecord {
+            key: vlad.clone().into(),
+        };
+        let peerpiper = self.peerpiper.clone();
+        Box::pin(async move {
+            let ReturnValues::Data(data) = order(&peerpiper, command).await? else {
+                return Err(TestError::PerrPiper(peerpiper::Error::NotConnected));
+            };
+            Ok(Cid::try_from(data.as_slice())?)
+        })
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum TestError {
     #[error("PeerPiper error: {0}")]
     PerrPiper(#[from] peerpiper::Error),
+    #[error("Multicid error: {0}")]
+    Multicid(#[from] multicid::Error),
 }